@@ -1,3 +1,5 @@
+use std::ops::{BitOr, BitOrAssign};
+
 use byteorder::{ByteOrder, LittleEndian};
 
 use crate::{Bits, LastBit, ToBool, Zero};
@@ -24,7 +26,7 @@ impl LastBit for u8 {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Bits8<const N: usize> {
     bits: [u8; N],
 }
@@ -129,6 +131,45 @@ impl<const N: usize> Default for Bits8<N> {
     }
 }
 
+impl<const N: usize> BitOrAssign for Bits8<N> {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.merge_value(0, rhs.bits.as_slice());
+    }
+}
+
+impl<const N: usize> BitOr for Bits8<N> {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: Self) -> Self::Output {
+        self |= rhs;
+        self
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Bits8;
+
+    impl<const N: usize> Serialize for Bits8<N> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.bits.as_slice().serialize(serializer)
+        }
+    }
+
+    impl<'de, const N: usize> Deserialize<'de> for Bits8<N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let bits = Vec::<u8>::deserialize(deserializer)?;
+            let len = bits.len();
+            let bits = bits
+                .try_into()
+                .map_err(|_| D::Error::invalid_length(len, &"as many slots as the store"))?;
+            Ok(Self { bits })
+        }
+    }
+}
+
 #[cfg(test)]
 mod utests {
     use super::*;
@@ -162,4 +203,39 @@ mod utests {
         assert_eq!(2, bits.slots());
         assert_eq!(9, bits.lsb());
     }
+
+    #[test]
+    fn bitor_() {
+        let mut xs = Bits8::<2>::default();
+        xs.set(1);
+        xs.set(9);
+
+        let mut ys = Bits8::<2>::default();
+        ys.set(2);
+        ys.set(9);
+
+        let zs = xs | ys;
+        assert!(zs.get(1));
+        assert!(zs.get(2));
+        assert!(zs.get(9));
+        assert!(!zs.get(3));
+
+        xs |= ys;
+        assert!(xs == zs);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_() {
+        let mut bits = Bits8::<2>::default();
+        bits.merge_u16(10 << 8);
+
+        let json = serde_json::to_string(&bits).unwrap();
+        assert_eq!("[0,10]", json);
+
+        let other: Bits8<2> = serde_json::from_str(&json).unwrap();
+        assert!(bits == other);
+
+        assert!(serde_json::from_str::<Bits8<3>>(&json).is_err());
+    }
 }
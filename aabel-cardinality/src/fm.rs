@@ -4,6 +4,8 @@ use std::{
 };
 
 use aabel_bits::{Bits, Bits8};
+use aabel_codec::{CodecError, Header, SketchKind};
+use aabel_hash::hash::HasherId;
 
 pub struct FlajoletMartin<H, const M: usize, const N: usize> {
     bits: [Bits8<N>; M],
@@ -34,6 +36,26 @@ where
         bs.set(j);
     }
 
+    /// Merges another sketch into this one, as if all its items were added
+    /// to this sketch. The type parameters guarantee both sketches use the
+    /// same number of bitmaps, the same bitmap size and the same hasher.
+    pub fn merge(&mut self, other: &Self) {
+        self.bits
+            .iter_mut()
+            .zip(other.bits.iter())
+            .for_each(|(bs, other)| *bs |= *other);
+    }
+
+    /// Returns a new sketch which is the union of the two sketches.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut fm = Self {
+            bits: self.bits,
+            _ph: PhantomData,
+        };
+        fm.merge(other);
+        fm
+    }
+
     pub fn n(&self) -> f64 {
         let r: usize = self.bits.iter().map(|bs| bs.lzb()).sum();
         3f64 * 2f64.powf(r as f64 / 3f64) / 0.77351
@@ -59,6 +81,89 @@ where
     }
 }
 
+impl<H, const M: usize, const N: usize> FlajoletMartin<H, M, N>
+where
+    H: Default + Hasher + HasherId,
+{
+    /// Encodes the bitmaps, their dimensions and the hasher identifier.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let params = vec![M as u64, N as u64];
+        let header = Header::new(SketchKind::FlajoletMartin, H::HASHER_ID, [0; 16], params);
+
+        let payload: Vec<u8> = self
+            .bits
+            .iter()
+            .flat_map(|bs| (0..N).map(|i| bs.get_slot(i)))
+            .collect();
+        header.encode(&payload)
+    }
+
+    /// Decodes the bitmaps encoded with [`FlajoletMartin::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (header, payload) = Header::decode(bytes)?;
+        header.expect_kind(SketchKind::FlajoletMartin)?;
+        header.expect_hasher(H::HASHER_ID)?;
+
+        let [m, n] = header.expect_params()?;
+        if m != M as u64 || n != N as u64 {
+            return Err(CodecError::InvalidParameters);
+        }
+
+        if payload.len() != M * N {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        let mut fm = Self::default();
+        for (bs, slots) in fm.bits.iter_mut().zip(payload.chunks_exact(N)) {
+            bs.merge_value(0, slots);
+        }
+
+        Ok(fm)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use std::marker::PhantomData;
+
+    use aabel_bits::Bits8;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::FlajoletMartin;
+
+    #[derive(Serialize)]
+    struct FlajoletMartinRef<'a, const N: usize> {
+        bits: &'a [Bits8<N>],
+    }
+
+    #[derive(Deserialize)]
+    struct FlajoletMartinRepr<const N: usize> {
+        bits: Vec<Bits8<N>>,
+    }
+
+    impl<H, const M: usize, const N: usize> Serialize for FlajoletMartin<H, M, N> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            FlajoletMartinRef { bits: &self.bits }.serialize(serializer)
+        }
+    }
+
+    impl<'de, H, const M: usize, const N: usize> Deserialize<'de> for FlajoletMartin<H, M, N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let repr = FlajoletMartinRepr::<N>::deserialize(deserializer)?;
+            let len = repr.bits.len();
+            let bits = repr
+                .bits
+                .try_into()
+                .map_err(|_| D::Error::invalid_length(len, &"as many bitmaps as the sketch"))?;
+
+            Ok(Self {
+                bits,
+                _ph: PhantomData,
+            })
+        }
+    }
+}
+
 #[cfg(test)]
 mod utests {
     use std::hash::Hash;
@@ -128,4 +233,76 @@ mod utests {
         assert!(12f64 < n);
         assert!(n < 13f64);
     }
+
+    #[test]
+    fn merge_() {
+        let mut single = FlajoletMartin::<MyHasher, 3, 8>::default();
+        let mut left = FlajoletMartin::<MyHasher, 3, 8>::default();
+        let mut right = FlajoletMartin::<MyHasher, 3, 8>::default();
+
+        for (i, city) in CITIES.iter().enumerate() {
+            single.add_item(city.0);
+
+            if i % 2 == 0 {
+                left.add_item(city.0);
+            } else {
+                right.add_item(city.0);
+            }
+        }
+
+        let union = left.union(&right);
+        assert_eq!(single.n(), union.n());
+
+        left.merge(&right);
+        assert_eq!(single.n(), left.n());
+        assert!(single.bits == left.bits);
+    }
+
+    #[test]
+    fn merge_overlapping_() {
+        let mut single = FlajoletMartin::<MyHasher, 3, 8>::default();
+        let mut left = FlajoletMartin::<MyHasher, 3, 8>::default();
+        let mut right = FlajoletMartin::<MyHasher, 3, 8>::default();
+
+        for city in CITIES.iter() {
+            single.add_item(city.0);
+        }
+
+        // Both partitions see the middle cities.
+        CITIES[..7].iter().for_each(|city| left.add_item(city.0));
+        CITIES[3..].iter().for_each(|city| right.add_item(city.0));
+
+        left.merge(&right);
+        assert_eq!(single.n(), left.n());
+    }
+
+    #[test]
+    fn bytes_() {
+        let mut fm = FlajoletMartin::<MyHasher, 3, 8>::default();
+        CITIES.iter().for_each(|city| fm.add_item(city.0));
+
+        let bytes = fm.to_bytes();
+        let other = FlajoletMartin::<MyHasher, 3, 8>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(fm.n(), other.n());
+        assert!(fm.bits == other.bits);
+
+        assert!(matches!(
+            FlajoletMartin::<MyHasher, 4, 8>::from_bytes(&bytes),
+            Err(CodecError::InvalidParameters)
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_() {
+        let mut fm = FlajoletMartin::<MyHasher, 3, 8>::default();
+        CITIES.iter().for_each(|city| fm.add_item(city.0));
+
+        let json = serde_json::to_string(&fm).unwrap();
+        let other: FlajoletMartin<MyHasher, 3, 8> = serde_json::from_str(&json).unwrap();
+        assert_eq!(fm.n(), other.n());
+
+        assert!(serde_json::from_str::<FlajoletMartin<MyHasher, 4, 8>>(&json).is_err());
+    }
 }
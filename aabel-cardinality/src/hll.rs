@@ -0,0 +1,184 @@
+use std::{
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use aabel_hash::hash::HashExt;
+
+/// Implements the HyperLogLog cardinality estimator with `2^P` registers.
+///
+/// The relative standard error of the estimate is `1.04 / sqrt(2^P)`, so
+/// `P = 14` gives about 0.8% error using 16KB of registers.
+pub struct HyperLogLog<H, const P: usize> {
+    registers: Vec<u8>,
+    _ph: PhantomData<H>,
+}
+
+impl<H, const P: usize> Default for HyperLogLog<H, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H, const P: usize> HyperLogLog<H, P> {
+    /// The number of registers.
+    const M: usize = 1 << P;
+
+    /// Creates a new estimator with all the registers set to zero.
+    pub fn new() -> Self {
+        assert!(
+            (4..=18).contains(&P),
+            "the precision must be between 4 and 18"
+        );
+
+        Self {
+            registers: vec![0; Self::M],
+            _ph: PhantomData,
+        }
+    }
+
+    /// Returns the number of registers.
+    pub fn registers(&self) -> usize {
+        Self::M
+    }
+
+    /// Returns the relative standard error of the estimator.
+    pub fn std_error(&self) -> f64 {
+        1.04 / (Self::M as f64).sqrt()
+    }
+
+    /// Returns the register index and the rank for a 64 bits hash value.
+    pub(crate) fn index_rank(h: u64) -> (usize, u8) {
+        let idx = (h >> (64 - P)) as usize;
+        let w = h << P;
+        let rank = (w.leading_zeros() as usize).min(64 - P) + 1;
+        (idx, rank as u8)
+    }
+
+    /// Keeps the maximum rank observed for a register.
+    pub(crate) fn update(&mut self, idx: usize, rank: u8) {
+        let r = &mut self.registers[idx];
+        if *r < rank {
+            *r = rank;
+        }
+    }
+
+    /// Returns the raw estimate and the number of registers still set to zero.
+    pub(crate) fn raw_estimate(&self) -> (f64, usize) {
+        let mut sum = 0f64;
+        let mut zeros = 0;
+
+        for &r in &self.registers {
+            sum += 1f64 / (1u64 << r) as f64;
+            if r == 0 {
+                zeros += 1;
+            }
+        }
+
+        let m = Self::M as f64;
+        (alpha(Self::M) * m * m / sum, zeros)
+    }
+
+    /// Returns the linear counting estimate given the number of empty registers.
+    pub(crate) fn linear_counting(zeros: usize) -> f64 {
+        let m = Self::M as f64;
+        m * (m / zeros as f64).ln()
+    }
+}
+
+impl<H, const P: usize> HyperLogLog<H, P>
+where
+    H: Default + Hasher,
+{
+    /// Hashes an item and raises the rank of its register.
+    pub fn add_item<T: Hash>(&mut self, item: T) {
+        let h = item.get_hash::<H>();
+        let (idx, rank) = Self::index_rank(h);
+        self.update(idx, rank);
+    }
+
+    /// Returns the estimated number of distinct items.
+    pub fn n(&self) -> f64 {
+        let (e, zeros) = self.raw_estimate();
+
+        // Small range correction, switch to linear counting.
+        if e <= 2.5 * Self::M as f64 && zeros > 0 {
+            return Self::linear_counting(zeros);
+        }
+
+        e
+    }
+}
+
+/// Returns the bias correction constant for a given number of registers.
+fn alpha(m: usize) -> f64 {
+    match m {
+        16 => 0.673,
+        32 => 0.697,
+        64 => 0.709,
+        _ => 0.7213 / (1.0 + 1.079 / m as f64),
+    }
+}
+
+#[cfg(test)]
+mod utests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+
+    #[test]
+    fn index_rank_() {
+        let (idx, rank) = HyperLogLog::<DefaultHasher, 4>::index_rank(0xA800_0000_0000_0000);
+        assert_eq!(0xA, idx);
+        assert_eq!(1, rank);
+
+        let (idx, rank) = HyperLogLog::<DefaultHasher, 4>::index_rank(0x1000_0000_0000_0001);
+        assert_eq!(1, idx);
+        assert_eq!(60, rank);
+
+        let (idx, rank) = HyperLogLog::<DefaultHasher, 4>::index_rank(0xF000_0000_0000_0000);
+        assert_eq!(0xF, idx);
+        assert_eq!(61, rank);
+    }
+
+    #[test]
+    fn duplicates_() {
+        let mut hll = HyperLogLog::<DefaultHasher, 10>::new();
+
+        for _ in 0..1000 {
+            hll.add_item("Athens");
+        }
+
+        let n = hll.n();
+        assert!(0.9 < n);
+        assert!(n < 1.1);
+    }
+
+    #[test]
+    fn small_range_() {
+        let mut hll = HyperLogLog::<DefaultHasher, 12>::new();
+
+        for i in 0..100u64 {
+            hll.add_item(i);
+        }
+
+        let n = hll.n();
+        println!("HLL={n}");
+        assert!(95f64 < n);
+        assert!(n < 105f64);
+    }
+
+    #[test]
+    fn large_range_() {
+        let mut hll = HyperLogLog::<DefaultHasher, 12>::new();
+
+        for i in 0..200_000u64 {
+            hll.add_item(i);
+        }
+
+        let n = hll.n();
+        let err = (n - 200_000f64).abs() / 200_000f64;
+        println!("HLL={n} ERR={err}");
+        assert!(err < 3f64 * hll.std_error());
+    }
+}
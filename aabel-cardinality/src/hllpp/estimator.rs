@@ -0,0 +1,201 @@
+use std::hash::{Hash, Hasher};
+
+use aabel_hash::hash::HashExt;
+
+use crate::HyperLogLog;
+
+use super::sparse::{self, SparseList, SPARSE_P};
+
+/// The smallest supported precision.
+const MIN_P: usize = 4;
+
+/// The cardinality under which linear counting is preferred, for each
+/// precision, as published by Heule et al.
+const THRESHOLDS: [f64; 15] = [
+    10.0, 20.0, 40.0, 80.0, 220.0, 400.0, 900.0, 1800.0, 3100.0, 6500.0, 11500.0, 20000.0, 50000.0,
+    120000.0, 350000.0,
+];
+
+enum Repr<H, const P: usize> {
+    Sparse(SparseList),
+    Dense(HyperLogLog<H, P>),
+}
+
+/// Implements the HyperLogLog++ cardinality estimator with `2^P` registers.
+///
+/// The estimator starts with a sparse representation, a sorted and compressed
+/// list of index/rank pairs at a higher precision, and converts itself into
+/// dense registers once the list grows larger than the registers would be.
+/// Estimates from the dense registers switch from linear counting to the raw
+/// estimate at the published thresholds. The empirical bias correction of
+/// Heule et al. is not applied, so mid-range estimates keep the bias of the
+/// raw HyperLogLog estimate.
+pub struct HyperLogLogPlus<H, const P: usize> {
+    repr: Repr<H, P>,
+}
+
+impl<H, const P: usize> Default for HyperLogLogPlus<H, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H, const P: usize> HyperLogLogPlus<H, P> {
+    /// The number of registers.
+    const M: usize = 1 << P;
+
+    /// Creates a new estimator in the sparse representation.
+    pub fn new() -> Self {
+        assert!(
+            (4..=18).contains(&P),
+            "the precision must be between 4 and 18"
+        );
+
+        Self {
+            repr: Repr::Sparse(SparseList::default()),
+        }
+    }
+
+    /// Returns true if the estimator still uses the sparse representation.
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.repr, Repr::Sparse(_))
+    }
+
+    /// Returns the relative standard error of the estimator once dense.
+    pub fn std_error(&self) -> f64 {
+        1.04 / (Self::M as f64).sqrt()
+    }
+
+    /// Merges the sparse buffer and converts to dense registers when the
+    /// sparse list no longer saves memory.
+    fn compact(&mut self) {
+        if let Repr::Sparse(list) = &mut self.repr {
+            if list.buffered() < Self::M / 4 {
+                return;
+            }
+
+            list.flush();
+            if list.size_in_bytes() > Self::M {
+                self.densify();
+            }
+        }
+    }
+
+    fn densify(&mut self) {
+        if let Repr::Sparse(list) = &self.repr {
+            let mut hll = HyperLogLog::<H, P>::new();
+            for k in list.entries() {
+                let (idx, rank) = sparse::decode(k, P);
+                hll.update(idx, rank);
+            }
+
+            self.repr = Repr::Dense(hll);
+        }
+    }
+}
+
+impl<H, const P: usize> HyperLogLogPlus<H, P>
+where
+    H: Default + Hasher,
+{
+    /// Hashes an item and adds it to the sparse list, or to the dense registers.
+    pub fn add_item<T: Hash>(&mut self, item: T) {
+        let h = item.get_hash::<H>();
+
+        match &mut self.repr {
+            Repr::Sparse(list) => {
+                list.insert(sparse::encode(h, P));
+                self.compact();
+            }
+            Repr::Dense(hll) => {
+                let (idx, rank) = HyperLogLog::<H, P>::index_rank(h);
+                hll.update(idx, rank);
+            }
+        }
+    }
+
+    /// Returns the estimated number of distinct items.
+    pub fn n(&self) -> f64 {
+        match &self.repr {
+            Repr::Sparse(list) => {
+                // Linear counting over the sparse registers.
+                let m = (1u64 << SPARSE_P) as f64;
+                let zeros = m - list.entries().len() as f64;
+                m * (m / zeros).ln()
+            }
+            Repr::Dense(hll) => {
+                let (e, zeros) = hll.raw_estimate();
+
+                let h = if zeros > 0 {
+                    HyperLogLog::<H, P>::linear_counting(zeros)
+                } else {
+                    e
+                };
+
+                if h <= THRESHOLDS[P - MIN_P] {
+                    h
+                } else {
+                    e
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod utests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+
+    fn estimate<const P: usize>(n: u64) -> HyperLogLogPlus<DefaultHasher, P> {
+        let mut hll = HyperLogLogPlus::<DefaultHasher, P>::new();
+        for i in 0..n {
+            hll.add_item(i);
+        }
+        hll
+    }
+
+    #[test]
+    fn sparse_() {
+        let hll = estimate::<14>(1000);
+        assert!(hll.is_sparse());
+
+        let n = hll.n();
+        println!("HLL++={n}");
+        assert!((n - 1000f64).abs() < 5f64);
+    }
+
+    #[test]
+    fn dense_() {
+        let hll = estimate::<10>(3000);
+        assert!(!hll.is_sparse());
+
+        let n = hll.n();
+        let err = (n - 3000f64).abs() / 3000f64;
+        println!("HLL++={n} ERR={err}");
+        assert!(err < 3f64 * hll.std_error());
+    }
+
+    #[test]
+    fn large_range_() {
+        let hll = estimate::<12>(200_000);
+        assert!(!hll.is_sparse());
+
+        let n = hll.n();
+        let err = (n - 200_000f64).abs() / 200_000f64;
+        println!("HLL++={n} ERR={err}");
+        assert!(err < 3f64 * hll.std_error());
+    }
+
+    #[test]
+    fn duplicates_() {
+        let mut hll = HyperLogLogPlus::<DefaultHasher, 10>::new();
+        for _ in 0..10_000 {
+            hll.add_item("Athens");
+        }
+
+        assert!(hll.is_sparse());
+        assert!((hll.n() - 1f64).abs() < 0.1);
+    }
+}
@@ -0,0 +1,4 @@
+mod estimator;
+mod sparse;
+
+pub use estimator::*;
@@ -0,0 +1,218 @@
+/// The precision used by the sparse representation.
+pub(crate) const SPARSE_P: usize = 25;
+
+/// Encodes a 64 bits hash into a sparse entry for a given dense precision.
+///
+/// The entry keeps the top `SPARSE_P` bits of the hash as index in its top
+/// bits, so entries sort by index. When the bits between the dense and the
+/// sparse index are all zero, the rank can not be recovered from the index so
+/// it is stored in the next 6 bits and the lowest bit is set as a flag.
+pub(crate) fn encode(h: u64, p: usize) -> u32 {
+    let idx = (h >> (64 - SPARSE_P)) as u32;
+
+    if idx & low_mask(p) == 0 {
+        let w = h << SPARSE_P;
+        let rank = (w.leading_zeros() as usize).min(64 - SPARSE_P) as u32 + 1;
+        (idx << 7) | (rank << 1) | 1
+    } else {
+        idx << 7
+    }
+}
+
+/// Returns the sparse index of an entry.
+pub(crate) fn sparse_index(k: u32) -> u32 {
+    k >> 7
+}
+
+/// Returns the dense register index and rank of an entry.
+pub(crate) fn decode(k: u32, p: usize) -> (usize, u8) {
+    let idx = sparse_index(k);
+    let shift = (SPARSE_P - p) as u32;
+
+    let rank = if k & 1 == 1 {
+        ((k >> 1) & 0x3f) + shift
+    } else {
+        let low = idx & low_mask(p);
+        shift - (32 - low.leading_zeros()) + 1
+    };
+
+    ((idx >> shift) as usize, rank as u8)
+}
+
+/// The mask for the sparse index bits which are not part of the dense index.
+fn low_mask(p: usize) -> u32 {
+    (1 << (SPARSE_P - p)) - 1
+}
+
+/// A sorted list of sparse entries, delta and varint compressed, with an
+/// unsorted buffer for the most recent insertions.
+#[derive(Default)]
+pub(crate) struct SparseList {
+    bytes: Vec<u8>,
+    len: usize,
+    buffer: Vec<u32>,
+}
+
+impl SparseList {
+    /// Adds an entry to the insertion buffer.
+    pub(crate) fn insert(&mut self, k: u32) {
+        self.buffer.push(k);
+    }
+
+    /// Returns the number of entries waiting in the insertion buffer.
+    pub(crate) fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns the size of the compressed list in bytes.
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Merges the insertion buffer into the compressed list.
+    pub(crate) fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let entries = self.entries();
+        self.buffer.clear();
+
+        self.bytes.clear();
+        self.len = entries.len();
+
+        let mut prev = 0u32;
+        for k in entries {
+            write_varint(&mut self.bytes, k - prev);
+            prev = k;
+        }
+    }
+
+    /// Returns the sorted entries, including the buffered ones, keeping for
+    /// each sparse index only the entry with the highest rank.
+    pub(crate) fn entries(&self) -> Vec<u32> {
+        let mut buffer = self.buffer.clone();
+        buffer.sort_unstable();
+
+        let mut entries = Vec::with_capacity(self.len + buffer.len());
+        let mut push = |k: u32| match entries.last_mut() {
+            Some(last) if sparse_index(*last) == sparse_index(k) => *last = k.max(*last),
+            _ => entries.push(k),
+        };
+
+        let mut compressed = self.iter().peekable();
+        let mut buffered = buffer.into_iter().peekable();
+
+        loop {
+            let k = match (compressed.peek(), buffered.peek()) {
+                (Some(&a), Some(&b)) if a <= b => compressed.next(),
+                (Some(_), Some(_)) => buffered.next(),
+                (Some(_), None) => compressed.next(),
+                (None, _) => buffered.next(),
+            };
+
+            match k {
+                Some(k) => push(k),
+                None => break,
+            }
+        }
+
+        entries
+    }
+
+    /// Iterates over the entries of the compressed list.
+    fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut pos = 0;
+        let mut prev = 0u32;
+
+        (0..self.len).map(move |_| {
+            prev += read_varint(&self.bytes, &mut pos);
+            prev
+        })
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut v: u32) {
+    while v >= 0x80 {
+        bytes.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+
+    bytes.push(v as u8);
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> u32 {
+    let mut v = 0u32;
+    let mut shift = 0;
+
+    loop {
+        let b = bytes[*pos];
+        *pos += 1;
+
+        v |= ((b & 0x7f) as u32) << shift;
+        if b & 0x80 == 0 {
+            return v;
+        }
+
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+
+    #[test]
+    fn varint_() {
+        let mut bytes = vec![];
+        for v in [0, 1, 127, 128, 300, u32::MAX] {
+            write_varint(&mut bytes, v);
+        }
+
+        let mut pos = 0;
+        for v in [0, 1, 127, 128, 300, u32::MAX] {
+            assert_eq!(v, read_varint(&bytes, &mut pos));
+        }
+        assert_eq!(bytes.len(), pos);
+    }
+
+    #[test]
+    fn encode_decode_() {
+        let p = 14;
+        let hashes = [
+            0xA800_0000_0000_0000u64,
+            0x0000_0400_0000_0000,
+            0x0000_0000_0000_0000,
+            0xFFFF_FFFF_FFFF_FFFF,
+            0x1234_5678_9ABC_DEF0,
+        ];
+
+        for h in hashes {
+            let idx = (h >> (64 - p)) as usize;
+            let rank = ((h << p).leading_zeros() as usize).min(64 - p) as u8 + 1;
+            assert_eq!((idx, rank), decode(encode(h, p), p));
+        }
+    }
+
+    #[test]
+    fn entries_() {
+        let p = 14;
+        let mut list = SparseList::default();
+
+        list.insert(encode(0x0000_0400_0000_0000, p));
+        list.insert(encode(0xA800_0000_0000_0000, p));
+        list.flush();
+
+        // Same sparse index, higher rank.
+        list.insert(encode(0x0000_0000_0000_0001, p));
+        list.insert(encode(0x0000_0000_0000_0100, p));
+
+        let entries = list.entries();
+        assert_eq!(3, entries.len());
+        assert!(entries.windows(2).all(|w| w[0] < w[1]));
+
+        list.flush();
+        assert_eq!(0, list.buffered());
+        assert_eq!(entries, list.entries());
+    }
+}
@@ -1,6 +1,10 @@
 mod fm;
+mod hll;
+mod hllpp;
+
 pub use fm::*;
+pub use hll::*;
+pub use hllpp::*;
 
 #[cfg(test)]
 pub mod myhasher;
-
@@ -1,5 +1,7 @@
 use std::hash::Hasher;
 
+use aabel_hash::hash::HasherId;
+
 pub const CITIES: [(&str, u64); 10] = [
     ("Athens", 4161497820),
     ("Berlin", 3680793991),
@@ -48,3 +50,7 @@ impl Hasher for MyHasher {
         self.bytes.extend_from_slice(bytes)
     }
 }
+
+impl HasherId for MyHasher {
+    const HASHER_ID: u8 = 128;
+}
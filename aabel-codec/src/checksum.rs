@@ -0,0 +1,21 @@
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Returns the FNV-1a checksum of a byte slice.
+pub(crate) fn checksum(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(FNV_OFFSET, |h, &b| (h ^ b as u64).wrapping_mul(FNV_PRIME))
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+
+    #[test]
+    fn checksum_() {
+        assert_eq!(0xcbf2_9ce4_8422_2325, checksum(b""));
+        assert_eq!(0xaf63_dc4c_8601_ec8c, checksum(b"a"));
+        assert_eq!(0x8594_4171_f739_67e8, checksum(b"foobar"));
+    }
+}
@@ -0,0 +1,51 @@
+use std::{error::Error, fmt::Display};
+
+use crate::SketchKind;
+
+/// The errors returned when decoding a sketch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    /// The buffer ends before the encoded data.
+    Truncated,
+    /// The buffer does not start with the expected magic bytes.
+    BadMagic,
+    /// The buffer was encoded with an unsupported format version.
+    UnsupportedVersion(u8),
+    /// The buffer stores an unknown type of sketch.
+    UnknownKind(u8),
+    /// The buffer stores a different type of sketch.
+    KindMismatch {
+        expected: SketchKind,
+        found: SketchKind,
+    },
+    /// The sketch was built with a different hasher.
+    HasherMismatch { expected: u8, found: u8 },
+    /// The checksum does not match the content of the buffer.
+    ChecksumMismatch,
+    /// The parameters do not match the sketch type.
+    InvalidParameters,
+    /// The payload does not match the parameters.
+    InvalidPayload,
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "the buffer is truncated"),
+            Self::BadMagic => write!(f, "the buffer does not contain a sketch"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported format version {v}"),
+            Self::UnknownKind(k) => write!(f, "unknown sketch type {k}"),
+            Self::KindMismatch { expected, found } => {
+                write!(f, "expected a {expected} sketch, found a {found} sketch")
+            }
+            Self::HasherMismatch { expected, found } => {
+                write!(f, "expected hasher {expected}, found hasher {found}")
+            }
+            Self::ChecksumMismatch => write!(f, "the checksum does not match"),
+            Self::InvalidParameters => write!(f, "invalid sketch parameters"),
+            Self::InvalidPayload => write!(f, "the payload does not match the parameters"),
+        }
+    }
+}
+
+impl Error for CodecError {}
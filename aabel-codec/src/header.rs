@@ -0,0 +1,227 @@
+use crate::{checksum::checksum, CodecError, SketchKind};
+
+/// The bytes every encoded sketch starts with.
+pub const MAGIC: [u8; 4] = *b"AABL";
+
+/// The current version of the binary format.
+///
+/// The format is not released yet and still changes without a version bump.
+/// Once released, every incompatible change to a sketch layout, its parameters
+/// or its hashing bumps the version.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// The header of an encoded sketch.
+///
+/// The binary format, with all the integers in little endian, is:
+///
+/// | field    | size           | description                            |
+/// |----------|----------------|----------------------------------------|
+/// | magic    | 4              | `AABL`                                 |
+/// | version  | 1              | the format version                     |
+/// | kind     | 1              | the sketch type, see [`SketchKind`]    |
+/// | hasher   | 1              | the hasher identifier                  |
+/// | seed     | 16             | the hasher seed, zero if not seeded    |
+/// | count    | 1              | the number of parameters               |
+/// | params   | 8 x count      | the sketch parameters                  |
+/// | length   | 8              | the payload length                     |
+/// | payload  | length         | the sketch content                     |
+/// | checksum | 8              | the FNV-1a hash of all previous bytes  |
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub kind: SketchKind,
+    pub hasher_id: u8,
+    pub seed: [u8; 16],
+    pub params: Vec<u64>,
+}
+
+impl Header {
+    pub fn new(kind: SketchKind, hasher_id: u8, seed: [u8; 16], params: Vec<u64>) -> Self {
+        Self {
+            kind,
+            hasher_id,
+            seed,
+            params,
+        }
+    }
+
+    /// Encodes the header followed by the payload and the checksum.
+    pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(40 + 8 * self.params.len() + payload.len());
+
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(FORMAT_VERSION);
+        bytes.push(self.kind as u8);
+        bytes.push(self.hasher_id);
+        bytes.extend_from_slice(&self.seed);
+
+        bytes.push(self.params.len() as u8);
+        for p in &self.params {
+            bytes.extend_from_slice(&p.to_le_bytes());
+        }
+
+        bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(payload);
+
+        let crc = checksum(&bytes);
+        bytes.extend_from_slice(&crc.to_le_bytes());
+
+        bytes
+    }
+
+    /// Decodes the header and returns it together with the payload.
+    pub fn decode(bytes: &[u8]) -> Result<(Self, &[u8]), CodecError> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        if reader.take(4)? != MAGIC {
+            return Err(CodecError::BadMagic);
+        }
+
+        let version = reader.u8()?;
+        if version != FORMAT_VERSION {
+            return Err(CodecError::UnsupportedVersion(version));
+        }
+
+        let kind = reader.u8()?;
+        let hasher_id = reader.u8()?;
+        let seed = reader.take(16)?.try_into().unwrap();
+
+        let count = reader.u8()? as usize;
+        let params = (0..count)
+            .map(|_| reader.u64())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let len = usize::try_from(reader.u64()?).map_err(|_| CodecError::Truncated)?;
+        let payload = reader.take(len)?;
+
+        let end = reader.pos;
+        if reader.u64()? != checksum(&bytes[..end]) {
+            return Err(CodecError::ChecksumMismatch);
+        }
+
+        let kind = SketchKind::try_from(kind)?;
+        let header = Self::new(kind, hasher_id, seed, params);
+        Ok((header, payload))
+    }
+
+    /// Checks the header describes a given type of sketch.
+    pub fn expect_kind(&self, kind: SketchKind) -> Result<(), CodecError> {
+        if self.kind == kind {
+            Ok(())
+        } else {
+            Err(CodecError::KindMismatch {
+                expected: kind,
+                found: self.kind,
+            })
+        }
+    }
+
+    /// Checks the sketch was built with a given hasher.
+    pub fn expect_hasher(&self, hasher_id: u8) -> Result<(), CodecError> {
+        if self.hasher_id == hasher_id {
+            Ok(())
+        } else {
+            Err(CodecError::HasherMismatch {
+                expected: hasher_id,
+                found: self.hasher_id,
+            })
+        }
+    }
+
+    /// Returns the parameters, checking there are as many as expected.
+    pub fn expect_params<const N: usize>(&self) -> Result<[u64; N], CodecError> {
+        self.params
+            .as_slice()
+            .try_into()
+            .map_err(|_| CodecError::InvalidParameters)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CodecError> {
+        let end = self.pos.checked_add(n).ok_or(CodecError::Truncated)?;
+        let bytes = self.bytes.get(self.pos..end).ok_or(CodecError::Truncated)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, CodecError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+
+    fn header() -> Header {
+        Header::new(SketchKind::BloomFilter, 2, [7; 16], vec![100, 10])
+    }
+
+    #[test]
+    fn roundtrip_() {
+        let bytes = header().encode(&[1, 2, 3]);
+        let (h, payload) = Header::decode(&bytes).unwrap();
+
+        assert_eq!(header(), h);
+        assert_eq!(&[1, 2, 3], payload);
+        assert_eq!([100, 10], h.expect_params::<2>().unwrap());
+        assert!(h.expect_params::<3>().is_err());
+    }
+
+    #[test]
+    fn corrupted_() {
+        let bytes = header().encode(&[1, 2, 3]);
+
+        let mut xs = bytes.clone();
+        xs[0] = b'X';
+        assert_eq!(Err(CodecError::BadMagic), Header::decode(&xs));
+
+        let mut xs = bytes.clone();
+        xs[4] = FORMAT_VERSION + 1;
+        assert_eq!(
+            Err(CodecError::UnsupportedVersion(FORMAT_VERSION + 1)),
+            Header::decode(&xs)
+        );
+
+        let mut xs = bytes.clone();
+        let n = xs.len();
+        xs[n - 10] ^= 0xff;
+        assert_eq!(Err(CodecError::ChecksumMismatch), Header::decode(&xs));
+
+        assert_eq!(
+            Err(CodecError::Truncated),
+            Header::decode(&bytes[..bytes.len() - 1])
+        );
+    }
+
+    #[test]
+    fn expect_() {
+        let h = header();
+        assert!(h.expect_kind(SketchKind::BloomFilter).is_ok());
+        assert!(h.expect_hasher(2).is_ok());
+
+        assert_eq!(
+            Err(CodecError::KindMismatch {
+                expected: SketchKind::CuckooFilter,
+                found: SketchKind::BloomFilter
+            }),
+            h.expect_kind(SketchKind::CuckooFilter)
+        );
+        assert_eq!(
+            Err(CodecError::HasherMismatch {
+                expected: 1,
+                found: 2
+            }),
+            h.expect_hasher(1)
+        );
+    }
+}
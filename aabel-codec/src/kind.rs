@@ -0,0 +1,40 @@
+use std::fmt::Display;
+
+use crate::CodecError;
+
+/// The type of sketch stored in an encoded buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SketchKind {
+    BloomFilter = 1,
+    BloomCounter = 2,
+    CuckooFilter = 3,
+    CountMinSketch = 4,
+    FlajoletMartin = 5,
+    SemiSortedCuckooFilter = 6,
+    CountingCuckooFilter = 7,
+    QuotientFilter = 8,
+}
+
+impl TryFrom<u8> for SketchKind {
+    type Error = CodecError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::BloomFilter),
+            2 => Ok(Self::BloomCounter),
+            3 => Ok(Self::CuckooFilter),
+            4 => Ok(Self::CountMinSketch),
+            5 => Ok(Self::FlajoletMartin),
+            6 => Ok(Self::SemiSortedCuckooFilter),
+            7 => Ok(Self::CountingCuckooFilter),
+            8 => Ok(Self::QuotientFilter),
+            _ => Err(CodecError::UnknownKind(value)),
+        }
+    }
+}
+
+impl Display for SketchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
@@ -0,0 +1,8 @@
+mod checksum;
+mod error;
+mod header;
+mod kind;
+
+pub use error::*;
+pub use header::*;
+pub use kind::*;
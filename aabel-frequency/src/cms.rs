@@ -1,6 +1,7 @@
 use std::{cmp::min, f64::consts, hash::Hash};
 
-use aabel_hash::hash::{Hasher128, SipHasher24};
+use aabel_codec::{CodecError, Header, SketchKind};
+use aabel_hash::hash::{Hash128Ext, HasherExt, HasherId, SipHasher24};
 
 /// Implements CountMin Sketch
 pub struct CountMinSketch<const M: usize, const K: usize> {
@@ -22,19 +23,16 @@ impl<const M: usize, const K: usize> CountMinSketch<M, K> {
     }
 
     pub fn insert<T: Hash>(&mut self, item: &T) {
-        self.get_indices(item, self.hasher)
-            .iter()
-            .enumerate()
-            .for_each(|(k, &idx)| {
-                self.counters[k][idx] = self.counters[k][idx].saturating_add(1);
-            });
+        for (k, idx) in self.get_indices(item, self.hasher).enumerate() {
+            self.counters[k][idx] = self.counters[k][idx].saturating_add(1);
+        }
         self.len += 1;
     }
 
     pub fn estimated_count<T: Hash>(&self, key: &T) -> u32 {
         let bucket_indices = self.get_indices(key, self.hasher);
         let mut estimated_count = u32::MAX;
-        for (ki, &bi) in bucket_indices.iter().enumerate() {
+        for (ki, bi) in bucket_indices.enumerate() {
             if self.counters[ki][bi] == 0 {
                 return 0;
             } else {
@@ -44,30 +42,54 @@ impl<const M: usize, const K: usize> CountMinSketch<M, K> {
         estimated_count
     }
 
-    fn get_indices<T: Hash>(&self, item: &T, hasher: SipHasher24) -> Vec<usize> {
-        let (hash1, hash2) = self.get_hash_pair(item, hasher);
-
-        let mut indices = Vec::with_capacity(K);
-        if K == 1 {
-            let bit = hash1 % M as u64;
-            indices.push(bit as usize);
-        } else {
-            for k in 0..K as u64 {
-                let hash = hash1.wrapping_add(k.wrapping_mul(hash2));
-                let bit = hash % M as u64;
-                indices.push(bit as usize);
-            }
+    /// Encodes the counters, their dimensions and the hasher seed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let params = vec![M as u64, K as u64, self.len as u64];
+        let header = Header::new(
+            SketchKind::CountMinSketch,
+            SipHasher24::HASHER_ID,
+            self.hasher.seed(),
+            params,
+        );
+
+        let payload: Vec<u8> = self
+            .counters
+            .iter()
+            .flatten()
+            .flat_map(|c| c.to_le_bytes())
+            .collect();
+        header.encode(&payload)
+    }
+
+    /// Decodes the counters encoded with [`CountMinSketch::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (header, payload) = Header::decode(bytes)?;
+        header.expect_kind(SketchKind::CountMinSketch)?;
+        header.expect_hasher(SipHasher24::HASHER_ID)?;
+
+        let [m, k, len] = header.expect_params()?;
+        if m != M as u64 || k != K as u64 {
+            return Err(CodecError::InvalidParameters);
+        }
+
+        if payload.len() != 4 * M * K {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        let mut counters = [[0; M]; K];
+        for (c, bytes) in counters.iter_mut().flatten().zip(payload.chunks_exact(4)) {
+            *c = u32::from_le_bytes(bytes.try_into().unwrap());
         }
 
-        indices
+        Ok(Self {
+            len: len as usize,
+            counters,
+            hasher: SipHasher24::with_seed(&header.seed),
+        })
     }
 
-    fn get_hash_pair<T: Hash>(&self, item: &T, mut hasher: SipHasher24) -> (u64, u64) {
-        item.hash(&mut hasher);
-        let hash128 = hasher.finish128().as_u128();
-        let hash1 = (hash128 & 0xffff_ffff_ffff_ffff) as u64;
-        let hash2 = (hash128 >> 64) as u64;
-        (hash1, hash2)
+    fn get_indices<T: Hash>(&self, item: &T, hasher: SipHasher24) -> impl Iterator<Item = usize> {
+        item.probes(K, hasher).map(|h| (h % M as u64) as usize)
     }
 }
 
@@ -95,6 +117,61 @@ pub fn optimal_k(delta: f64) -> usize {
     (1.0 / delta).ln().ceil() as usize
 }
 
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use aabel_hash::hash::{HasherExt, SipHasher24};
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::CountMinSketch;
+
+    #[derive(Serialize)]
+    struct CountMinSketchRef<'a> {
+        len: usize,
+        counters: Vec<&'a [u32]>,
+        seed: [u8; 16],
+    }
+
+    #[derive(Deserialize)]
+    struct CountMinSketchRepr {
+        len: usize,
+        counters: Vec<Vec<u32>>,
+        seed: [u8; 16],
+    }
+
+    impl<const M: usize, const K: usize> Serialize for CountMinSketch<M, K> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            CountMinSketchRef {
+                len: self.len,
+                counters: self.counters.iter().map(|row| row.as_slice()).collect(),
+                seed: self.hasher.seed(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, const M: usize, const K: usize> Deserialize<'de> for CountMinSketch<M, K> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let repr = CountMinSketchRepr::deserialize(deserializer)?;
+            if repr.counters.len() != K || repr.counters.iter().any(|row| row.len() != M) {
+                return Err(D::Error::custom(
+                    "the counters do not match the sketch size",
+                ));
+            }
+
+            let mut counters = [[0; M]; K];
+            for (row, other) in counters.iter_mut().zip(repr.counters) {
+                row.copy_from_slice(&other);
+            }
+
+            Ok(Self {
+                len: repr.len,
+                counters,
+                hasher: SipHasher24::with_seed(&repr.seed),
+            })
+        }
+    }
+}
+
 #[cfg(test)]
 mod utests {
     use super::*;
@@ -124,4 +201,38 @@ mod utests {
         assert_eq!(cms.estimated_count(&"a2"), 1000000);
         assert_eq!(cms.estimated_count(&"b1"), 0);
     }
+
+    #[test]
+    fn bytes_() {
+        let mut cms = CountMinSketch::<14, 5>::new();
+        for i in 0..1000 {
+            cms.insert(&(i % 7));
+        }
+
+        let bytes = cms.to_bytes();
+        let other = CountMinSketch::<14, 5>::from_bytes(&bytes).unwrap();
+
+        assert!((0..7).all(|i| cms.estimated_count(&i) == other.estimated_count(&i)));
+        assert_eq!(bytes, other.to_bytes());
+
+        assert!(matches!(
+            CountMinSketch::<14, 4>::from_bytes(&bytes),
+            Err(CodecError::InvalidParameters)
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_() {
+        let mut cms = CountMinSketch::<14, 5>::new();
+        for i in 0..1000 {
+            cms.insert(&(i % 7));
+        }
+
+        let json = serde_json::to_string(&cms).unwrap();
+        let other: CountMinSketch<14, 5> = serde_json::from_str(&json).unwrap();
+        assert!((0..7).all(|i| cms.estimated_count(&i) == other.estimated_count(&i)));
+
+        assert!(serde_json::from_str::<CountMinSketch<14, 4>>(&json).is_err());
+    }
 }
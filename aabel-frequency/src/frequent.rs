@@ -2,6 +2,14 @@ use std::collections::hash_map::IntoIter;
 use std::collections::HashMap;
 use std::hash::Hash;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: serde::Serialize",
+        deserialize = "T: Eq + Hash + serde::Deserialize<'de>"
+    ))
+)]
 pub struct Frequent<const N: usize, T> {
     items: HashMap<T, usize>,
 }
@@ -60,4 +68,15 @@ mod utests {
         let res = Frequent::<2, _>::frequent(xs);
         println!("res={:?}", res);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_() {
+        let mut frequent = Frequent::<2, u64>::default();
+        [4, 4, 6, 4].iter().for_each(|x| frequent.update(*x));
+
+        let json = serde_json::to_string(&frequent).unwrap();
+        let other: Frequent<2, u64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(frequent.items, other.items);
+    }
 }
@@ -1,4 +1,5 @@
 /// Implementation of the Boyer-Moore algorithm.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Majority<T> {
     counter: usize,
     element: Option<T>,
@@ -54,4 +55,17 @@ mod utests {
         assert!(res.is_some());
         assert_eq!(&1, res.unwrap());
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_() {
+        let mut majority = Majority::new();
+        [1, 2, 1].iter().for_each(|x| majority.update(*x));
+
+        let json = serde_json::to_string(&majority).unwrap();
+        let mut other: Majority<i32> = serde_json::from_str(&json).unwrap();
+
+        other.update(3);
+        assert_eq!(Some(1), other.element);
+    }
 }
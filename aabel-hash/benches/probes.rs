@@ -0,0 +1,52 @@
+//! Compares the allocating `get_hashes` with the allocation free `probes` when
+//! computing the bit indexes of a Bloom filter.
+//!
+//! Run it with `cargo bench -p aabel-hash --bench probes`.
+
+use aabel_hash::hash::{Hash128Ext, SipHasher24};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const M: usize = 9_585_058;
+const ITEMS: u64 = 10_000;
+
+fn indexes(c: &mut Criterion) {
+    let hasher = SipHasher24::new_with_keys(7, 11);
+
+    let mut group = c.benchmark_group("indexes");
+    group.throughput(Throughput::Elements(ITEMS));
+
+    for k in [4, 7, 14] {
+        group.bench_with_input(BenchmarkId::new("get_hashes", k), &k, |b, &k| {
+            b.iter(|| {
+                (0..ITEMS)
+                    .map(|x| {
+                        let idxs: Vec<usize> = black_box(x)
+                            .get_hashes(k, hasher)
+                            .iter()
+                            .map(|h| (*h as usize) % M)
+                            .collect();
+                        idxs.iter().sum::<usize>()
+                    })
+                    .sum::<usize>()
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("probes", k), &k, |b, &k| {
+            b.iter(|| {
+                (0..ITEMS)
+                    .map(|x| {
+                        black_box(x)
+                            .probes(k, hasher)
+                            .map(|h| (h as usize) % M)
+                            .sum::<usize>()
+                    })
+                    .sum::<usize>()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, indexes);
+criterion_main!(benches);
@@ -2,6 +2,8 @@ use std::hash::{BuildHasherDefault, Hasher};
 
 use cityhash::cityhash_1::city_hash_64;
 
+use crate::hasher_id::HasherId;
+
 #[derive(Default)]
 pub struct City64Hasher {
     bytes: Vec<u8>,
@@ -17,6 +19,10 @@ impl Hasher for City64Hasher {
     }
 }
 
+impl HasherId for City64Hasher {
+    const HASHER_ID: u8 = 4;
+}
+
 /// A builder for default CITYHASH-64 hashers.
 pub type City64BuildHasher = BuildHasherDefault<City64Hasher>;
 
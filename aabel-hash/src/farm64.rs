@@ -1,6 +1,8 @@
 use farmhash::hash64;
 use std::hash::{BuildHasherDefault, Hasher};
 
+use crate::hasher_id::HasherId;
+
 /// A hasher that uses the Google's [farm](https://github.com/google/farmhash) algorithm.
 #[derive(Default)]
 pub struct Farm64Hasher {
@@ -17,6 +19,10 @@ impl Hasher for Farm64Hasher {
     }
 }
 
+impl HasherId for Farm64Hasher {
+    const HASHER_ID: u8 = 5;
+}
+
 /// A builder for default FARMHASH-64 hashers.
 pub type Farm64BuildHasher = BuildHasherDefault<Farm64Hasher>;
 
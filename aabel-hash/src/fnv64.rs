@@ -2,6 +2,8 @@ use std::hash::{BuildHasherDefault, Hasher};
 
 use const_fnv1a_hash::fnv1a_hash_64;
 
+use crate::hasher_id::HasherId;
+
 /// A hasher that uses the Google's [Fowler–Noll–Vo](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function) algorithm.
 #[derive(Default)]
 pub struct Fnv64Hasher {
@@ -18,6 +20,10 @@ impl Hasher for Fnv64Hasher {
     }
 }
 
+impl HasherId for Fnv64Hasher {
+    const HASHER_ID: u8 = 3;
+}
+
 /// A builder for default FNV-64 hashers.
 pub type Fnv64BuildHasher = BuildHasherDefault<Fnv64Hasher>;
 
@@ -16,6 +16,9 @@ pub trait Hash128Ext: Hash {
         self.get_hash128_deconstructed(hasher)
     }
 
+    /// Returns the *k* hashes for the instance, collected in a vector.
+    ///
+    /// Prefer [`Hash128Ext::probes`] on hot paths, it does not allocate.
     fn get_hashes<H>(&self, k: usize, hasher: H) -> Vec<u64>
     where
         H: Hasher + Hasher128,
@@ -30,8 +33,69 @@ pub trait Hash128Ext: Hash {
 
         bs
     }
+
+    /// Returns an iterator over *k* hashes for the instance, generated with
+    /// enhanced double hashing from a single 128 bits hash.
+    fn probes<H>(&self, k: usize, hasher: H) -> Probes
+    where
+        H: Hasher + Hasher128,
+    {
+        let (hash1, hash2) = self.get_hash128_deconstructed(hasher);
+        Probes::new(hash1, hash2, k)
+    }
+}
+
+/// An allocation free sequence of hashes, generated with enhanced double
+/// hashing (Dillinger & Manolios).
+///
+/// Plain double hashing, `h1 + i * h2`, degenerates when `h2` is zero or
+/// shares factors with the table size, as all the probes then collapse on a
+/// few positions. Adding a growing offset to `h2` at each step avoids it.
+#[derive(Clone, Debug)]
+pub struct Probes {
+    x: u64,
+    y: u64,
+    i: u64,
+    k: u64,
+}
+
+impl Probes {
+    /// Creates the sequence of *k* hashes derived from two hash values.
+    pub fn new(hash1: u64, hash2: u64, k: usize) -> Self {
+        Self {
+            x: hash1,
+            y: hash2,
+            i: 0,
+            k: k as u64,
+        }
+    }
+}
+
+impl Iterator for Probes {
+    type Item = u64;
+
+    #[inline]
+    fn next(&mut self) -> Option<u64> {
+        if self.i == self.k {
+            return None;
+        }
+
+        let h = self.x;
+        self.i += 1;
+        self.y = self.y.wrapping_add(self.i);
+        self.x = self.x.wrapping_add(self.y);
+
+        Some(h)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = (self.k - self.i) as usize;
+        (n, Some(n))
+    }
 }
 
+impl ExactSizeIterator for Probes {}
+
 impl<T> Hash128Ext for T
 where
     T: Hash,
@@ -49,3 +113,23 @@ where
         (hash1, hash2)
     }
 }
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+
+    #[test]
+    fn probes_() {
+        let hs: Vec<u64> = Probes::new(10, 3, 5).collect();
+        assert_eq!(vec![10, 14, 20, 29, 42], hs);
+        assert_eq!(7, Probes::new(1, 2, 7).len());
+    }
+
+    #[test]
+    fn probes_zero_hash2_() {
+        // Plain double hashing would return the same hash k times.
+        let hs: Vec<u64> = Probes::new(42, 0, 4).collect();
+        assert_eq!(vec![42, 43, 46, 52], hs);
+        assert_eq!(4, hs.iter().collect::<std::collections::HashSet<_>>().len());
+    }
+}
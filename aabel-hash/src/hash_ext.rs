@@ -41,7 +41,7 @@ where
         H: Default + Hasher,
     {
         let h = <Self as HashExt>::get_hash::<H>(self);
-        let h1 = (h & 0xfff_ffff) as u32;
+        let h1 = (h & 0xffff_ffff) as u32;
         let h2 = (h >> 32) as u32;
         (h1, h2)
     }
@@ -60,5 +60,6 @@ mod utests {
         assert_ne!(h, 0);
         assert_ne!(h1, 0);
         assert_ne!(h2, 0);
+        assert_eq!(h, (h2 as u64) << 32 | h1 as u64);
     }
 }
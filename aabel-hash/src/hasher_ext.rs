@@ -4,4 +4,7 @@ pub trait HasherExt {
 
     /// Create a hasher with a random seed.
     fn with_rnd_seed() -> Self;
+
+    /// Returns the seed the hasher was created with.
+    fn seed(&self) -> [u8; 16];
 }
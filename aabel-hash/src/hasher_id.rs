@@ -0,0 +1,9 @@
+/// Identifies a hasher algorithm, so persisted data can be checked against
+/// the hasher used to read it back. Only hashers whose algorithm is fixed
+/// implement it, so the `DefaultHasher` of the standard library, which may
+/// change between Rust releases, does not. The identifier 1 is retired, and
+/// the identifiers from 128 to 255 are reserved for hashers defined outside
+/// of this crate.
+pub trait HasherId {
+    const HASHER_ID: u8;
+}
@@ -10,6 +10,7 @@ mod fnv64;
 mod hash128_ext;
 mod hash_ext;
 mod hasher_ext;
+mod hasher_id;
 
 #[cfg(feature = "murmur")]
 mod murmur32;
@@ -21,6 +22,7 @@ pub mod hash {
     pub use crate::hash128_ext::*;
     pub use crate::hash_ext::*;
     pub use crate::hasher_ext::*;
+    pub use crate::hasher_id::*;
 
     #[cfg(feature = "city")]
     pub use crate::city64::*;
@@ -34,9 +36,6 @@ pub mod hash {
     #[cfg(feature = "murmur")]
     pub use crate::murmur32::*;
 
-    #[cfg(feature = "sip")]
-    pub use siphasher::sip128::*;
-
     #[cfg(feature = "sip")]
     pub use crate::sip24::*;
 }
@@ -1,7 +1,9 @@
-use std::hash::Hasher;
+use std::hash::{BuildHasherDefault, Hasher};
 
 use const_murmur3::murmur3_32;
 
+use crate::hasher_id::HasherId;
+
 pub struct Murmur32Hasher {
     seed: u32,
     bytes: Vec<u8>,
@@ -33,6 +35,10 @@ impl Hasher for Murmur32Hasher {
     }
 }
 
+impl HasherId for Murmur32Hasher {
+    const HASHER_ID: u8 = 6;
+}
+
 /// A builder for default MURMUR3-32 hashers.
 pub type Murmur32BuildHasher = BuildHasherDefault<Murmur32Hasher>;
 
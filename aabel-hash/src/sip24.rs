@@ -1,6 +1,6 @@
-use siphasher::sip128::SipHasher24;
+pub use siphasher::sip128::*;
 
-use crate::hasher_ext::HasherExt;
+use crate::{hasher_ext::HasherExt, hasher_id::HasherId};
 
 impl HasherExt for SipHasher24 {
     fn with_seed(seed: &[u8; 16]) -> Self {
@@ -14,4 +14,12 @@ impl HasherExt for SipHasher24 {
 
         Self::with_seed(seed)
     }
+
+    fn seed(&self) -> [u8; 16] {
+        self.key()
+    }
+}
+
+impl HasherId for SipHasher24 {
+    const HASHER_ID: u8 = 2;
 }
@@ -0,0 +1,110 @@
+//! Compares the query throughput and the false positive rate of the standard
+//! and the blocked Bloom filters, and the batch queries with the single ones.
+//!
+//! Run it with `cargo bench -p aabel-membership --bench bloom`.
+
+use aabel_membership::bloom::{BlockedBloomFilter, BloomFilter};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const ITEMS: usize = 1_000_000;
+const FPR: f64 = 0.01;
+const QUERIES: usize = 100_000;
+
+/// The batch queries run against a filter larger than the CPU caches.
+const BATCH_ITEMS: usize = 20_000_000;
+
+fn measured_fpr(contains: impl Fn(&usize) -> bool) -> f64 {
+    let fps = (ITEMS..ITEMS + QUERIES).filter(|x| contains(x)).count();
+    fps as f64 / QUERIES as f64
+}
+
+fn contains(c: &mut Criterion) {
+    let mut bloom = BloomFilter::<usize>::with_capacity_fpr(ITEMS, FPR);
+    let mut blocked = BlockedBloomFilter::<usize>::with_capacity_fpr(ITEMS, FPR);
+    for x in 0..ITEMS {
+        bloom.insert(&x);
+        blocked.insert(&x);
+    }
+
+    println!(
+        "bloom: bits={} k={} fpr={:.4}",
+        bloom.number_of_bits(),
+        bloom.number_of_hashes(),
+        measured_fpr(|x| bloom.contains(x))
+    );
+    println!(
+        "blocked: bits={} k={} fpr={:.4}",
+        blocked.number_of_bits(),
+        blocked.number_of_hashes(),
+        measured_fpr(|x| blocked.contains(x))
+    );
+
+    // Half of the queries hit inserted items, half miss.
+    let queries: Vec<usize> = (0..QUERIES).map(|i| i * 37 % (2 * ITEMS)).collect();
+
+    let mut group = c.benchmark_group("contains");
+    group.throughput(Throughput::Elements(QUERIES as u64));
+
+    group.bench_function(BenchmarkId::new("bloom", ITEMS), |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .filter(|x| bloom.contains(black_box(x)))
+                .count()
+        })
+    });
+    group.bench_function(BenchmarkId::new("blocked", ITEMS), |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .filter(|x| blocked.contains(black_box(x)))
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert");
+    group.throughput(Throughput::Elements(QUERIES as u64));
+
+    group.bench_function(BenchmarkId::new("bloom", ITEMS), |b| {
+        let mut bloom = BloomFilter::<usize>::with_capacity_fpr(ITEMS, FPR);
+        b.iter(|| (0..QUERIES).for_each(|x| bloom.insert(black_box(&x))))
+    });
+    group.bench_function(BenchmarkId::new("blocked", ITEMS), |b| {
+        let mut blocked = BlockedBloomFilter::<usize>::with_capacity_fpr(ITEMS, FPR);
+        b.iter(|| (0..QUERIES).for_each(|x| blocked.insert(black_box(&x))))
+    });
+
+    group.finish();
+}
+
+fn contains_many(c: &mut Criterion) {
+    let mut bloom = BloomFilter::<usize>::with_capacity_fpr(BATCH_ITEMS, FPR);
+    let xs: Vec<usize> = (0..BATCH_ITEMS).collect();
+    bloom.insert_many(&xs);
+
+    let queries: Vec<usize> = (0..QUERIES).map(|i| i * 37 % (2 * BATCH_ITEMS)).collect();
+
+    let mut group = c.benchmark_group("batch");
+    group.throughput(Throughput::Elements(QUERIES as u64));
+
+    group.bench_function(BenchmarkId::new("contains", BATCH_ITEMS), |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .map(|x| bloom.contains(black_box(x)))
+                .collect::<Vec<bool>>()
+        })
+    });
+    group.bench_function(BenchmarkId::new("contains_many", BATCH_ITEMS), |b| {
+        b.iter(|| bloom.contains_many(black_box(&queries)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, contains, insert, contains_many);
+criterion_main!(benches);
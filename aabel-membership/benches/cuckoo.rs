@@ -0,0 +1,109 @@
+//! Compares the bits per item, the false positive rate and the throughput of
+//! the cuckoo filter with four slots per bucket and of the semi-sorted one.
+//!
+//! Run it with `cargo bench -p aabel-membership --bench cuckoo`.
+
+use aabel_membership::cuckoo::{CuckooFilter, SemiSortedCuckooFilter};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const BUCKETS: usize = 1 << 14;
+
+/// The filters are filled up to 90% of their slots.
+const ITEMS: usize = BUCKETS * 4 * 9 / 10;
+const QUERIES: usize = 100_000;
+
+fn measured_fpr(contains: impl Fn(&usize) -> bool) -> f64 {
+    let fps = (ITEMS..ITEMS + QUERIES).filter(|x| contains(x)).count();
+    fps as f64 / QUERIES as f64
+}
+
+fn report(name: &str, bits: usize, len: usize, fpr: f64) {
+    println!(
+        "{name}: bits={bits} items={len} bits/item={:.2} fpr={fpr:.5}",
+        bits as f64 / len as f64
+    );
+}
+
+fn contains(c: &mut Criterion) {
+    let mut cuckoo = Box::new(CuckooFilter::<BUCKETS, 4, 8>::new());
+    let mut semi8 = SemiSortedCuckooFilter::<BUCKETS, 8>::new();
+    let mut semi9 = SemiSortedCuckooFilter::<BUCKETS, 9>::new();
+    for x in 0..ITEMS {
+        let _ = cuckoo.insert(&x);
+        let _ = semi8.insert(&x);
+        let _ = semi9.insert(&x);
+    }
+
+    // With 8 bits fingerprints, the semi-sorted buckets have the same rate with
+    // fewer bits, and with 9 bits a lower rate with the same bits.
+    report(
+        "cuckoo/8",
+        cuckoo.number_of_bits(),
+        cuckoo.len(),
+        measured_fpr(|x| cuckoo.contains(x)),
+    );
+    report(
+        "semi-sorted/8",
+        semi8.number_of_bits(),
+        semi8.len(),
+        measured_fpr(|x| semi8.contains(x)),
+    );
+    report(
+        "semi-sorted/9",
+        semi9.number_of_bits(),
+        semi9.len(),
+        measured_fpr(|x| semi9.contains(x)),
+    );
+
+    // Half of the queries hit inserted items, half miss.
+    let queries: Vec<usize> = (0..QUERIES).map(|i| i * 37 % (2 * ITEMS)).collect();
+
+    let mut group = c.benchmark_group("contains");
+    group.throughput(Throughput::Elements(QUERIES as u64));
+
+    group.bench_function(BenchmarkId::new("cuckoo", ITEMS), |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .filter(|x| cuckoo.contains(black_box(x)))
+                .count()
+        })
+    });
+    group.bench_function(BenchmarkId::new("semi-sorted", ITEMS), |b| {
+        b.iter(|| {
+            queries
+                .iter()
+                .filter(|x| semi9.contains(black_box(x)))
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert");
+    group.throughput(Throughput::Elements(ITEMS as u64));
+
+    group.bench_function(BenchmarkId::new("cuckoo", ITEMS), |b| {
+        b.iter(|| {
+            let mut cuckoo = Box::new(CuckooFilter::<BUCKETS, 4, 8>::new());
+            (0..ITEMS).for_each(|x| {
+                let _ = cuckoo.insert(black_box(&x));
+            })
+        })
+    });
+    group.bench_function(BenchmarkId::new("semi-sorted", ITEMS), |b| {
+        b.iter(|| {
+            let mut semi = SemiSortedCuckooFilter::<BUCKETS, 9>::new();
+            (0..ITEMS).for_each(|x| {
+                let _ = semi.insert(black_box(&x));
+            })
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, contains, insert);
+criterion_main!(benches);
@@ -0,0 +1,206 @@
+use aabel_hash::hash::{Hash128Ext, Hasher128, HasherExt, SipHasher24};
+use std::{
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+use super::compute_optimal;
+
+/// A Bloom filter which can be shared between threads without a lock.
+///
+/// The bits are stored in atomic words, so `insert` sets them with `fetch_or`
+/// and `contains` reads them with plain atomic loads. Bits are only ever set,
+/// so relaxed ordering is enough for an item to be found once its insertion
+/// returned. With the same seed and dimensions the filter sets the same bits
+/// as a `BloomFilter`.
+pub struct AtomicBloomFilter<T, H = SipHasher24> {
+    words: Vec<AtomicU64>,
+    /// Number of inserted items.
+    len: AtomicUsize,
+    // Number of bits
+    m: usize,
+    /// Number of hash functions.
+    k: usize,
+    hasher: H,
+    // The filter does not own any item, so it is `Sync` whatever `T` is.
+    _p: PhantomData<fn(&T)>,
+}
+
+impl<T, H> AtomicBloomFilter<T, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt,
+    T: Hash,
+{
+    /// Creates an `AtomicBloomFilter` with *m* number of bits and *k* number of hash functions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if *k* is 0.
+    pub fn new(m: usize, k: usize) -> Self {
+        let hasher = <H as HasherExt>::with_rnd_seed();
+        Self::with_hasher(m, k, hasher)
+    }
+
+    /// Creates an `AtomicBloomFilter` with *m* number of bits and *k* number of hash
+    /// functions, with the hasher seeded from a given seed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if *k* is 0.
+    pub fn with_seed(m: usize, k: usize, seed: &[u8; 16]) -> Self {
+        let hasher = <H as HasherExt>::with_seed(seed);
+        Self::with_hasher(m, k, hasher)
+    }
+
+    /// Creates a new atomic Bloom filter which is expected to store a given number of
+    /// elements and with an expected false positive rate.
+    pub fn with_capacity_fpr(num_items: usize, false_positive_rate: f64) -> Self {
+        let (m, k) = compute_optimal(num_items, false_positive_rate);
+        Self::new(m, k)
+    }
+
+    /// Creates a new atomic Bloom filter which is expected to store a given number of
+    /// elements and with an expected false positive rate, with the hasher seeded
+    /// from a given seed.
+    pub fn with_capacity_fpr_seed(
+        num_items: usize,
+        false_positive_rate: f64,
+        seed: &[u8; 16],
+    ) -> Self {
+        let (m, k) = compute_optimal(num_items, false_positive_rate);
+        Self::with_seed(m, k, seed)
+    }
+
+    fn with_hasher(m: usize, k: usize, hasher: H) -> Self {
+        assert!(k > 0, "the number of hash functions must be positive");
+
+        let words = (0..m.div_ceil(64)).map(|_| AtomicU64::new(0)).collect();
+
+        Self {
+            words,
+            len: AtomicUsize::new(0),
+            m,
+            k,
+            hasher,
+            _p: PhantomData,
+        }
+    }
+
+    /// Returns the number of bits in the filter
+    pub fn number_of_bits(&self) -> usize {
+        self.m
+    }
+
+    /// Returns the number of hash functions.
+    pub fn number_of_hashes(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the seed of the hasher.
+    pub fn seed(&self) -> [u8; 16] {
+        self.hasher.seed()
+    }
+
+    /// Returns the number of items inserted in the filter.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// Determines if no item was inserted in the filter.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts a new item in the filter.
+    pub fn insert(&self, item: &T) {
+        for idx in self.get_indexes(item) {
+            self.words[idx / 64].fetch_or(1 << (idx % 64), Ordering::Relaxed);
+        }
+
+        self.len.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Determines if an item belongs to the filter.
+    pub fn contains(&self, item: &T) -> bool {
+        self.get_indexes(item)
+            .all(|idx| self.words[idx / 64].load(Ordering::Relaxed) & (1 << (idx % 64)) != 0)
+    }
+
+    /// Returns the number of bits set in the filter.
+    pub fn number_of_set_bits(&self) -> usize {
+        self.words
+            .iter()
+            .map(|w| w.load(Ordering::Relaxed).count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns the ratio of bits set in the filter.
+    pub fn fill_ratio(&self) -> f64 {
+        self.number_of_set_bits() as f64 / self.m as f64
+    }
+
+    /// Returns the current false positive rate, the probability that all the
+    /// bits of an item which was not inserted are set.
+    pub fn current_fpr(&self) -> f64 {
+        self.fill_ratio().powi(self.k as i32)
+    }
+
+    /// Returns the indexes that represent a givenitem
+    fn get_indexes(&self, item: &T) -> impl Iterator<Item = usize> {
+        let m = self.m;
+        item.probes(self.k, self.hasher)
+            .map(move |h| (h as usize) % m)
+    }
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+    use crate::bloom::BloomFilter;
+    use quickcheck_macros::quickcheck;
+    use std::thread;
+
+    #[test]
+    fn simple_() {
+        let filter = AtomicBloomFilter::<usize>::new(100, 10);
+        filter.insert(&10);
+        assert!(filter.contains(&10));
+        assert_eq!(1, filter.len());
+    }
+
+    #[test]
+    fn threads_() {
+        const THREADS: usize = 8;
+        const ITEMS: usize = 20_000;
+
+        let seed = [42; 16];
+        let filter =
+            AtomicBloomFilter::<usize>::with_capacity_fpr_seed(THREADS * ITEMS, 0.01, &seed);
+
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let filter = &filter;
+                s.spawn(move || {
+                    (t * ITEMS..(t + 1) * ITEMS).for_each(|x| filter.insert(&x));
+                });
+            }
+        });
+
+        assert_eq!(THREADS * ITEMS, filter.len());
+        assert!((0..THREADS * ITEMS).all(|x| filter.contains(&x)));
+
+        // No insert was lost, the same bits are set as when inserting sequentially.
+        let mut expected =
+            BloomFilter::<usize>::with_capacity_fpr_seed(THREADS * ITEMS, 0.01, &seed);
+        (0..THREADS * ITEMS).for_each(|x| expected.insert(&x));
+        assert_eq!(expected.number_of_set_bits(), filter.number_of_set_bits());
+    }
+
+    #[quickcheck]
+    fn prop_atomic_bloom_filter(xs: Vec<usize>) -> bool {
+        let filter = AtomicBloomFilter::<usize>::new(100000, 10);
+        xs.iter().for_each(|x| filter.insert(x));
+        xs.iter().all(|x| filter.contains(x))
+    }
+}
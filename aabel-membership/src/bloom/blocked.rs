@@ -0,0 +1,302 @@
+use aabel_hash::hash::{Hash128Ext, Hasher128, HasherExt, SipHasher24};
+use std::{
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use super::compute_optimal;
+
+/// The number of 64 bits words in a block.
+const WORDS: usize = 8;
+
+/// The number of bits in a block.
+const BLOCK_BITS: usize = WORDS * 64;
+
+/// The maximum number of hash functions.
+const MAX_K: usize = 2 * WORDS;
+
+/// Odd multipliers used to derive the bit position of each probe.
+const SALTS: [u64; MAX_K] = [
+    0x47b6_137b_4497_4d91,
+    0x8824_ad5b_a2b7_289d,
+    0x7054_95c7_2df1_424b,
+    0x9efc_4947_5c6b_fb31,
+    0xd2bd_f2a3_8a45_e1d5,
+    0x6e4d_8b96_8f27_b6f7,
+    0x5c6b_fb31_a2b7_289d,
+    0xe6b9_c0c3_d7b1_e0e9,
+    0x9e37_79b9_7f4a_7c15,
+    0xbf58_476d_1ce4_e5b9,
+    0x94d0_49bb_1331_11eb,
+    0xc2b2_ae3d_27d4_eb4f,
+    0x1656_67b1_9e37_79f9,
+    0x27d4_eb2f_1656_67c5,
+    0xff51_afd7_ed55_8ccd,
+    0xc4ce_b9fe_1a85_ec53,
+];
+
+/// A cache line sized block of bits.
+#[derive(Clone, Copy, Default)]
+#[repr(align(64))]
+struct Block([u64; WORDS]);
+
+impl Block {
+    /// Returns the masks of the bits of a key, one mask per word.
+    #[inline]
+    fn masks(key: u64, k: usize) -> [u64; WORDS] {
+        let mut masks = [0u64; WORDS];
+        for (i, salt) in SALTS.iter().enumerate().take(k) {
+            let bit = key.wrapping_mul(*salt) >> 58;
+            masks[i % WORDS] |= 1 << bit;
+        }
+        masks
+    }
+
+    #[inline]
+    fn set(&mut self, masks: &[u64; WORDS]) {
+        for (w, m) in self.0.iter_mut().zip(masks) {
+            *w |= m;
+        }
+    }
+
+    #[inline]
+    fn check(&self, masks: &[u64; WORDS]) -> bool {
+        self.0.iter().zip(masks).all(|(w, m)| w & m == *m)
+    }
+}
+
+/// A Bloom filter which confines all the probes of an item to a single
+/// 64 bytes block, so a lookup touches only one cache line.
+///
+/// Each probe sets one bit in one of the eight words of the block, which keeps
+/// the word operations independent and easy to vectorize. For the same number
+/// of bits the false positive rate is slightly higher than the one of a
+/// `BloomFilter`, so `with_capacity_fpr` sizes the filter accordingly.
+pub struct BlockedBloomFilter<T, H = SipHasher24> {
+    blocks: Vec<Block>,
+    /// Number of inserted items.
+    len: usize,
+    /// Number of hash functions.
+    k: usize,
+    hasher: H,
+    _p: PhantomData<T>,
+}
+
+impl<T, H> BlockedBloomFilter<T, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt,
+    T: Hash,
+{
+    /// Creates a `BlockedBloomFilter` with at least *m* number of bits and *k* number
+    /// of hash functions. The number of bits is rounded up to a multiple of 512.
+    ///
+    /// # Panics
+    ///
+    /// Panics if *k* is not between 1 and 16.
+    pub fn new(m: usize, k: usize) -> Self {
+        let hasher = <H as HasherExt>::with_rnd_seed();
+        Self::with_hasher(m, k, hasher)
+    }
+
+    /// Creates a `BlockedBloomFilter` with at least *m* number of bits and *k* number
+    /// of hash functions, with the hasher seeded from a given seed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if *k* is not between 1 and 16.
+    pub fn with_seed(m: usize, k: usize, seed: &[u8; 16]) -> Self {
+        let hasher = <H as HasherExt>::with_seed(seed);
+        Self::with_hasher(m, k, hasher)
+    }
+
+    /// Creates a new blocked Bloom filter which is expected to store a given number
+    /// of elements and with an expected false positive rate.
+    pub fn with_capacity_fpr(num_items: usize, false_positive_rate: f64) -> Self {
+        let (m, k) = compute_optimal_blocked(num_items, false_positive_rate);
+        Self::new(m, k)
+    }
+
+    /// Creates a new blocked Bloom filter which is expected to store a given number
+    /// of elements and with an expected false positive rate, with the hasher seeded
+    /// from a given seed.
+    pub fn with_capacity_fpr_seed(
+        num_items: usize,
+        false_positive_rate: f64,
+        seed: &[u8; 16],
+    ) -> Self {
+        let (m, k) = compute_optimal_blocked(num_items, false_positive_rate);
+        Self::with_seed(m, k, seed)
+    }
+
+    fn with_hasher(m: usize, k: usize, hasher: H) -> Self {
+        assert!(
+            (1..=MAX_K).contains(&k),
+            "the number of hash functions must be between 1 and 16"
+        );
+
+        let blocks = vec![Block::default(); m.div_ceil(BLOCK_BITS).max(1)];
+
+        Self {
+            blocks,
+            len: 0,
+            k,
+            hasher,
+            _p: PhantomData,
+        }
+    }
+
+    /// Returns the number of bits in the filter
+    pub fn number_of_bits(&self) -> usize {
+        self.blocks.len() * BLOCK_BITS
+    }
+
+    /// Returns the number of blocks in the filter.
+    pub fn number_of_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns the number of hash functions.
+    pub fn number_of_hashes(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the seed of the hasher.
+    pub fn seed(&self) -> [u8; 16] {
+        self.hasher.seed()
+    }
+
+    /// Returns the number of items inserted in the filter.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Determines if no item was inserted in the filter.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a new item in the filter.
+    pub fn insert(&mut self, item: &T) {
+        let (idx, masks) = self.probe(item);
+        self.blocks[idx].set(&masks);
+
+        self.len += 1;
+    }
+
+    /// Determines if an item belongs to the filter.
+    pub fn contains(&self, item: &T) -> bool {
+        let (idx, masks) = self.probe(item);
+        self.blocks[idx].check(&masks)
+    }
+
+    /// Returns the block of an item and the bits to check within it.
+    #[inline]
+    fn probe(&self, item: &T) -> (usize, [u64; WORDS]) {
+        let (h1, h2) = item.get_hash128_deconstructed(self.hasher);
+
+        // Maps the hash to a block without a division.
+        let idx = ((h1 as u128 * self.blocks.len() as u128) >> 64) as usize;
+        (idx, Block::masks(h2, self.k))
+    }
+}
+
+/// Returns the number of bits and hash functions for a blocked filter.
+///
+/// Items are not spread evenly over the blocks, so the filter starts from the
+/// size of a standard filter and grows until the rate, averaged over the
+/// Poisson distributed block loads, reaches the target.
+fn compute_optimal_blocked(items: usize, false_positive_rate: f64) -> (usize, usize) {
+    let (mut m, k) = compute_optimal(items, false_positive_rate);
+    let k = k.min(MAX_K);
+
+    while blocked_fpr(items, m.div_ceil(BLOCK_BITS), k) > false_positive_rate {
+        m += m / 16 + BLOCK_BITS;
+    }
+
+    (m, k)
+}
+
+/// Returns the expected false positive rate of a blocked filter.
+fn blocked_fpr(items: usize, blocks: usize, k: usize) -> f64 {
+    let lambda = items as f64 / blocks.max(1) as f64;
+    let max = (lambda + 10.0 * lambda.sqrt() + 10.0) as usize;
+
+    // The number of probes which land in each word.
+    let probes = |w: usize| (k + WORDS - 1 - w) / WORDS;
+
+    let mut p = (-lambda).exp();
+    let mut fpr = 0.0;
+    for j in 0..=max {
+        if j > 0 {
+            p *= lambda / j as f64;
+        }
+
+        let hit = (0..WORDS)
+            .map(|w| {
+                let n = probes(w) as i32;
+                let set = 1.0 - (1.0 - 1.0 / 64.0f64).powi(j as i32 * n);
+                set.powi(n)
+            })
+            .product::<f64>();
+        fpr += p * hit;
+    }
+
+    fpr
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn simple_() {
+        let mut filter = BlockedBloomFilter::<usize>::new(100, 10);
+        assert_eq!(512, filter.number_of_bits());
+
+        filter.insert(&10);
+        assert!(filter.contains(&10));
+        assert_eq!(1, filter.len());
+    }
+
+    #[test]
+    fn block_() {
+        assert_eq!(64, std::mem::align_of::<Block>());
+        assert_eq!(64, std::mem::size_of::<Block>());
+    }
+
+    #[test]
+    fn fpr_() {
+        let mut filter = BlockedBloomFilter::<usize>::with_capacity_fpr(10_000, 0.01);
+        (0..10_000).for_each(|x| filter.insert(&x));
+
+        let fps = (10_000..110_000).filter(|x| filter.contains(x)).count();
+        let measured = fps as f64 / 100_000.0;
+        assert!(measured < 0.015, "measured={measured}");
+    }
+
+    #[test]
+    fn seed_() {
+        let mut xs = BlockedBloomFilter::<usize>::with_capacity_fpr_seed(1000, 0.01, &[1u8; 16]);
+        let mut ys = BlockedBloomFilter::<usize>::with_capacity_fpr_seed(1000, 0.01, &[1u8; 16]);
+        (0..1000).for_each(|x| xs.insert(&x));
+        (0..1000).for_each(|x| ys.insert(&x));
+
+        assert_eq!([1u8; 16], xs.seed());
+        assert_eq!(xs.number_of_bits(), ys.number_of_bits());
+        assert!((0..10_000).all(|x| xs.contains(&x) == ys.contains(&x)));
+    }
+
+    #[test]
+    #[should_panic(expected = "the number of hash functions must be between 1 and 16")]
+    fn too_many_hashes_() {
+        BlockedBloomFilter::<usize>::new(1000, 17);
+    }
+
+    #[quickcheck]
+    fn prop_blocked_bloom_filter(xs: Vec<usize>) -> bool {
+        let mut filter = BlockedBloomFilter::<usize>::new(10_000, 8);
+        xs.iter().for_each(|x| filter.insert(x));
+        xs.iter().all(|x| filter.contains(x))
+    }
+}
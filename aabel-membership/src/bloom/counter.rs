@@ -3,12 +3,41 @@ use std::{
     marker::PhantomData,
 };
 
-use aabel_hash::hash::{Hash128Ext, Hasher128, HasherExt, SipHasher24};
+use aabel_codec::{CodecError, Header, SketchKind};
+use aabel_hash::hash::{Hash128Ext, Hasher128, HasherExt, HasherId, SipHasher24};
 
-use super::compute_optimal;
+use crate::prefetch::{prefetch, BATCH};
 
+use super::{
+    compute_optimal, BloomError, CounterWidth, OverflowPolicy, PackedCounters, SideTable,
+    UpdateMode,
+};
+
+/// The outcome of [`BloomCounter::try_delete`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteOutcome {
+    /// The item was verified as inserted and removed from the filter.
+    Removed,
+    /// The item does not belong to the filter, nothing was changed.
+    NotPresent,
+    /// The filter reports the item but it could not be verified as inserted,
+    /// so it may be a false positive and nothing was changed.
+    PossiblyFalsePositive,
+    /// The counters of the item are all frozen at their largest value, so it
+    /// cannot be removed and nothing was changed.
+    Frozen,
+}
+
+/// A counting Bloom filter, which supports deletes and counts.
+///
+/// The counters are 8 bits wide, saturate and are all incremented on insert by
+/// default, `with_counters` selects another width and overflow policy and
+/// `with_update_mode` another update mode.
 pub struct BloomCounter<T, H = SipHasher24> {
-    counters: Vec<u8>,
+    counters: PackedCounters,
+    policy: OverflowPolicy,
+    mode: UpdateMode,
+    verify: Option<SideTable>,
     len: usize,
     m: usize,
     k: usize,
@@ -22,27 +51,63 @@ where
     T: Hash,
 {
     /// Creates a new Bloom filter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if *k* is 0.
     pub fn new(m: usize, k: usize) -> Self {
-        let counters = vec![0; m];
         let hasher = <H as HasherExt>::with_rnd_seed();
+        Self::with_hasher(m, k, hasher)
+    }
+
+    /// Creates a new Bloom filter with the hasher seeded from a given seed.
+    /// Filters created with the same seed and dimensions are compatible.
+    ///
+    /// # Panics
+    ///
+    /// Panics if *k* is 0.
+    pub fn with_seed(m: usize, k: usize, seed: &[u8; 16]) -> Self {
+        let hasher = <H as HasherExt>::with_seed(seed);
+        Self::with_hasher(m, k, hasher)
+    }
+
+    /// Creates a new Bloom filter which is expected to store a given number of
+    /// elements and with an expected false positive rate.
+    pub fn with_capacity_fpr(num_items: usize, false_positive_rate: f64) -> Self {
+        let (m, k) = compute_optimal(num_items, false_positive_rate);
+        Self::new(m, k)
+    }
+
+    /// Creates a new Bloom filter which is expected to store a given number of
+    /// elements and with an expected false positive rate, with the hasher seeded
+    /// from a given seed.
+    pub fn with_capacity_fpr_seed(
+        num_items: usize,
+        false_positive_rate: f64,
+        seed: &[u8; 16],
+    ) -> Self {
+        let (m, k) = compute_optimal(num_items, false_positive_rate);
+        Self::with_seed(m, k, seed)
+    }
+
+    fn with_hasher(m: usize, k: usize, hasher: H) -> Self {
+        assert!(k > 0, "the number of hash functions must be positive");
+
+        let counters = PackedCounters::new(m, CounterWidth::Bits8);
 
         Self {
             m,
             k,
             counters,
+            policy: OverflowPolicy::Saturate,
+            mode: UpdateMode::Standard,
+            verify: None,
             len: 0,
             hasher,
             _p: PhantomData,
         }
     }
 
-    /// Creates a new Bloom filter which is expected to store a given number of
-    /// elements and with an expected false positive rate.
-    pub fn with_capacity_fpr(num_items: usize, false_positive_rate: f64) -> Self {
-        let (m, k) = compute_optimal(num_items, false_positive_rate);
-        Self::new(m, k)
-    }
-
     /// Returns the number of bits in the filter
     pub fn number_of_bits(&self) -> usize {
         self.m
@@ -53,66 +118,495 @@ where
         self.k
     }
 
+    /// Returns the seed of the hasher.
+    pub fn seed(&self) -> [u8; 16] {
+        self.hasher.seed()
+    }
+
+    /// Returns the number of items inserted in the filter.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Determines if no item was inserted in the filter.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the filter with counters of a given width and overflow policy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if items were already inserted in the filter.
+    pub fn with_counters(mut self, width: CounterWidth, policy: OverflowPolicy) -> Self {
+        assert!(
+            self.len == 0,
+            "the counters can be changed only on an empty filter"
+        );
+
+        self.counters = PackedCounters::new(self.m, width);
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the filter with a given update mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if items were already inserted in the filter.
+    pub fn with_update_mode(mut self, mode: UpdateMode) -> Self {
+        assert!(
+            self.len == 0,
+            "the update mode can be changed only on an empty filter"
+        );
+
+        self.mode = mode;
+        self
+    }
+
+    /// Returns the filter with a side table which keeps the hashes of the last
+    /// `capacity` distinct inserted items, so [`BloomCounter::try_delete`] can
+    /// verify them. The side table is not encoded nor serialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if items were already inserted in the filter.
+    pub fn with_verification(mut self, capacity: usize) -> Self {
+        assert!(
+            self.len == 0,
+            "the verification can be enabled only on an empty filter"
+        );
+
+        self.verify = Some(SideTable::new(capacity));
+        self
+    }
+
+    /// Returns the capacity of the verification side table, if enabled.
+    pub fn verification_capacity(&self) -> Option<usize> {
+        self.verify.as_ref().map(|t| t.capacity())
+    }
+
+    /// Returns the width of the counters.
+    pub fn counter_width(&self) -> CounterWidth {
+        self.counters.width()
+    }
+
+    /// Returns the policy applied when a counter overflows.
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Returns how the counters are updated on insert.
+    pub fn update_mode(&self) -> UpdateMode {
+        self.mode
+    }
+
+    /// Returns the number of counters which reached their largest value.
+    ///
+    /// With the `Saturate` policy these counters are frozen, so the counts of
+    /// the items mapped to them are lower bounds and deleting them is partial.
+    pub fn saturated_counters(&self) -> usize {
+        self.counters.saturated(self.m)
+    }
+
+    /// Determines if any counter of an item is frozen at its largest value.
+    pub fn is_saturated(&self, item: &T) -> bool {
+        self.policy == OverflowPolicy::Saturate
+            && self
+                .get_indexes(item)
+                .any(|idx| self.counters.get(idx) == self.counters.width().max())
+    }
+
     /// Returns the indexes that represent a givenitem
-    fn get_indexes(&self, item: &T) -> Vec<usize> {
-        item.get_hashes(self.k, self.hasher)
-            .iter()
-            .map(|h| (*h as usize) % self.m)
-            .collect()
+    fn get_indexes(&self, item: &T) -> impl Iterator<Item = usize> + Clone {
+        let m = self.m;
+        item.probes(self.k, self.hasher)
+            .map(move |h| (h as usize) % m)
     }
 
     /// Inserts an item in the bloom filter.
-    pub fn insert(&mut self, item: &T) {
-        self.get_indexes(item)
-            .iter()
-            .for_each(|&idx| self.counters[idx] = self.counters[idx].saturating_add(1));
+    ///
+    /// With the `Error` policy, an insertion which would overflow a counter
+    /// leaves the filter unchanged and returns [`BloomError::CounterOverflow`].
+    pub fn insert(&mut self, item: &T) -> Result<(), BloomError> {
+        let idxs = self.get_indexes(item);
+        self.increment(idxs)?;
 
-        self.len += 1;
+        self.inserted(item);
+        Ok(())
     }
 
     /// Determines if an item belongs to the filter.
     pub fn contains(&self, item: &T) -> bool {
-        self.get_indexes(item)
-            .iter()
-            .all(|&idx| self.counters[idx] > 0)
+        self.get_indexes(item).all(|idx| self.counters.get(idx) > 0)
+    }
+
+    /// Increments the counters of an item, applying the update mode and the overflow policy.
+    fn increment(&mut self, idxs: impl Iterator<Item = usize> + Clone) -> Result<(), BloomError> {
+        let max = self.counters.width().max();
+
+        if self.mode == UpdateMode::Conservative {
+            let min = idxs.clone().map(|idx| self.counters.get(idx)).min();
+            let min = min.unwrap_or(0);
+
+            if min == max {
+                return match self.policy {
+                    OverflowPolicy::Saturate => Ok(()),
+                    OverflowPolicy::Error => Err(BloomError::CounterOverflow),
+                };
+            }
+
+            // A counter probed twice is incremented once, it is no longer the minimum.
+            for idx in idxs {
+                if self.counters.get(idx) == min {
+                    self.counters.set(idx, min + 1);
+                }
+            }
+
+            return Ok(());
+        }
+
+        for (i, idx) in idxs.clone().enumerate() {
+            let c = self.counters.get(idx);
+            if c < max {
+                self.counters.set(idx, c + 1);
+                continue;
+            }
+
+            if self.policy == OverflowPolicy::Error {
+                // Roll back the counters incremented so far.
+                for idx in idxs.take(i) {
+                    let c = self.counters.get(idx);
+                    self.counters.set(idx, c - 1);
+                }
+
+                return Err(BloomError::CounterOverflow);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inserts a batch of items in the filter.
+    ///
+    /// The items are hashed ahead of updating their counters, so the memory
+    /// loads of several items overlap. On an overflow error the items before
+    /// the failing one stay inserted.
+    pub fn insert_many(&mut self, items: &[T]) -> Result<(), BloomError> {
+        let mut idxs = Vec::with_capacity(BATCH * self.k);
+
+        for chunk in items.chunks(BATCH) {
+            self.batch_indexes(chunk, &mut idxs);
+            for (item, idxs) in chunk.iter().zip(idxs.chunks(self.k)) {
+                self.increment(idxs.iter().copied())?;
+                self.inserted(item);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Determines for each item of a batch if it belongs to the filter.
+    pub fn contains_many(&self, items: &[T]) -> Vec<bool> {
+        let mut res = Vec::with_capacity(items.len());
+        let mut idxs = Vec::with_capacity(BATCH * self.k);
+
+        for chunk in items.chunks(BATCH) {
+            self.batch_indexes(chunk, &mut idxs);
+            res.extend((0..chunk.len()).map(|i| {
+                idxs[i * self.k..(i + 1) * self.k]
+                    .iter()
+                    .all(|&idx| self.counters.get(idx) > 0)
+            }));
+        }
+
+        res
     }
 
+    /// Collects the indexes of a batch of items and prefetches their counters.
+    fn batch_indexes(&self, items: &[T], idxs: &mut Vec<usize>) {
+        idxs.clear();
+
+        for item in items {
+            for idx in self.get_indexes(item) {
+                prefetch(self.counters.bytes(), self.counters.byte_index(idx));
+                idxs.push(idx);
+            }
+        }
+    }
+
+    /// Deletes an item from the filter, if it belongs to it.
+    ///
+    /// With the `Saturate` policy, frozen counters are not decremented. With the
+    /// `Conservative` update mode, deleting may cause false negatives.
     pub fn delete(&mut self, item: &T) {
-        if self.contains(item) {
-            self.get_indexes(item)
-                .iter()
-                .for_each(|&idx| self.counters[idx] = self.counters[idx].saturating_sub(1));
+        if self.contains(item) && self.decrement(item) {
+            if let Some(table) = &mut self.verify {
+                table.remove(Self::key(item, self.hasher));
+            }
+        }
+    }
+
+    /// Deletes an item from the filter only if it can be verified as inserted,
+    /// so deleting a false positive never corrupts the counters of other items.
+    ///
+    /// Items are verified with the side table enabled by
+    /// [`BloomCounter::with_verification`]. Without it, or once an item was
+    /// evicted from it, [`DeleteOutcome::PossiblyFalsePositive`] is returned and
+    /// [`BloomCounter::delete`] can still force the removal.
+    pub fn try_delete(&mut self, item: &T) -> DeleteOutcome {
+        if !self.contains(item) {
+            return DeleteOutcome::NotPresent;
+        }
+
+        let max = self.counters.width().max();
+        if self.policy == OverflowPolicy::Saturate
+            && self
+                .get_indexes(item)
+                .all(|idx| self.counters.get(idx) == max)
+        {
+            return DeleteOutcome::Frozen;
+        }
+
+        let key = Self::key(item, self.hasher);
+        let verified = self.verify.as_mut().is_some_and(|t| t.remove(key));
+
+        if verified {
+            self.decrement(item);
+            DeleteOutcome::Removed
+        } else {
+            DeleteOutcome::PossiblyFalsePositive
+        }
+    }
 
-            self.len -= 1;
+    /// Records an item whose counters were incremented.
+    fn inserted(&mut self, item: &T) {
+        if let Some(table) = &mut self.verify {
+            table.insert(Self::key(item, self.hasher));
         }
+
+        self.len += 1;
     }
 
-    pub fn count(&self, item: &T) -> u8 {
-        let mut c = u8::MAX;
+    /// Decrements the counters of an item, except the frozen ones, and returns
+    /// false if no counter was changed.
+    fn decrement(&mut self, item: &T) -> bool {
+        let max = self.counters.width().max();
+        let frozen = self.policy == OverflowPolicy::Saturate;
 
+        let mut changed = false;
         for idx in self.get_indexes(item) {
-            if self.counters[idx] == 0 {
-                return 0;
+            let c = self.counters.get(idx);
+            if c > 0 && !(frozen && c == max) {
+                self.counters.set(idx, c - 1);
+                changed = true;
             }
+        }
+
+        // Deleting false positives may remove more items than were inserted.
+        if changed {
+            self.len = self.len.saturating_sub(1);
+        }
+
+        changed
+    }
+
+    /// Returns the key of an item in the side table, its 128 bits hash.
+    fn key(item: &T, hasher: H) -> u128 {
+        let (hash1, hash2) = item.get_hash128_deconstructed(hasher);
+        ((hash2 as u128) << 64) | hash1 as u128
+    }
+
+    /// Returns the number of times an item was inserted, or an upper bound of it.
+    pub fn count(&self, item: &T) -> u32 {
+        self.get_indexes(item)
+            .map(|idx| self.counters.get(idx))
+            .min()
+            .unwrap_or(0)
+    }
 
-            if self.counters[idx] < c {
-                c = self.counters[idx];
+    /// Returns the 128 bits hash of an item, which identifies it exactly.
+    pub(crate) fn item_key(&self, item: &T) -> u128 {
+        Self::key(item, self.hasher)
+    }
+
+    /// Returns the smallest counter of an item and whether it is shared by
+    /// more than one of its counters.
+    pub(crate) fn recurring_minimum(&self, item: &T) -> (u32, bool) {
+        let mut min = u32::MAX;
+        let mut times = 0;
+
+        for idx in self.get_indexes(item) {
+            let c = self.counters.get(idx);
+            if c < min {
+                min = c;
+                times = 1;
+            } else if c == min {
+                times += 1;
+            }
+        }
+
+        (min, times > 1)
+    }
+
+    /// Raises the counters of an item below a given value to that value, and
+    /// counts the item as inserted.
+    pub(crate) fn raise(&mut self, item: &T, value: u32) {
+        let value = value.min(self.counters.width().max());
+
+        for idx in self.get_indexes(item) {
+            if self.counters.get(idx) < value {
+                self.counters.set(idx, value);
+            }
+        }
+
+        self.len += 1;
+    }
+}
+
+impl<T, H> BloomCounter<T, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt + HasherId,
+    T: Hash,
+{
+    /// Encodes the counters, their parameters and the hasher seed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let params = vec![
+            self.m as u64,
+            self.k as u64,
+            self.len as u64,
+            self.counters.width().bits() as u64,
+            self.policy.id(),
+            self.mode.id(),
+        ];
+        let header = Header::new(
+            SketchKind::BloomCounter,
+            H::HASHER_ID,
+            self.hasher.seed(),
+            params,
+        );
+        header.encode(self.counters.bytes())
+    }
+
+    /// Decodes the counters encoded with [`BloomCounter::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (header, payload) = Header::decode(bytes)?;
+        header.expect_kind(SketchKind::BloomCounter)?;
+        header.expect_hasher(H::HASHER_ID)?;
+
+        let [m, k, len, width, policy, mode] = header.expect_params()?;
+        let (m, k, len) = (m as usize, k as usize, len as usize);
+        if m == 0 || k == 0 {
+            return Err(CodecError::InvalidParameters);
+        }
+
+        let width = CounterWidth::from_bits(width as usize).ok_or(CodecError::InvalidParameters)?;
+        let policy = OverflowPolicy::from_id(policy).ok_or(CodecError::InvalidParameters)?;
+        let mode = UpdateMode::from_id(mode).ok_or(CodecError::InvalidParameters)?;
+        let counters = PackedCounters::from_bytes(m, width, payload.to_vec())
+            .ok_or(CodecError::InvalidPayload)?;
+
+        Ok(Self {
+            counters,
+            policy,
+            mode,
+            verify: None,
+            len,
+            m,
+            k,
+            hasher: H::with_seed(&header.seed),
+            _p: PhantomData,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use std::marker::PhantomData;
+
+    use aabel_hash::hash::HasherExt;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{BloomCounter, CounterWidth, OverflowPolicy, PackedCounters, UpdateMode};
+
+    #[derive(Serialize)]
+    struct BloomCounterRef<'a> {
+        m: usize,
+        k: usize,
+        len: usize,
+        seed: [u8; 16],
+        width: CounterWidth,
+        policy: OverflowPolicy,
+        mode: UpdateMode,
+        counters: &'a [u8],
+    }
+
+    #[derive(Deserialize)]
+    struct BloomCounterRepr {
+        m: usize,
+        k: usize,
+        len: usize,
+        seed: [u8; 16],
+        width: CounterWidth,
+        policy: OverflowPolicy,
+        mode: UpdateMode,
+        counters: Vec<u8>,
+    }
+
+    impl<T, H> Serialize for BloomCounter<T, H>
+    where
+        H: HasherExt,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            BloomCounterRef {
+                m: self.m,
+                k: self.k,
+                len: self.len,
+                seed: self.hasher.seed(),
+                width: self.counters.width(),
+                policy: self.policy,
+                mode: self.mode,
+                counters: self.counters.bytes(),
             }
+            .serialize(serializer)
         }
+    }
+
+    impl<'de, T, H> Deserialize<'de> for BloomCounter<T, H>
+    where
+        H: HasherExt,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let repr = BloomCounterRepr::deserialize(deserializer)?;
+            let counters = PackedCounters::from_bytes(repr.m, repr.width, repr.counters)
+                .filter(|_| repr.m > 0 && repr.k > 0)
+                .ok_or_else(|| D::Error::custom("the counters do not match the filter size"))?;
 
-        c
+            Ok(Self {
+                counters,
+                policy: repr.policy,
+                mode: repr.mode,
+                verify: None,
+                len: repr.len,
+                m: repr.m,
+                k: repr.k,
+                hasher: H::with_seed(&repr.seed),
+                _p: PhantomData,
+            })
+        }
     }
 }
 
 #[cfg(test)]
 mod utests {
     use super::*;
+    use crate::bloom::BloomFilter;
     use quickcheck_macros::quickcheck;
 
     #[test]
     fn simple_() {
         let mut filter = BloomCounter::<usize>::new(100, 10);
-        filter.insert(&10);
+        filter.insert(&10).unwrap();
 
         let res = filter.contains(&10);
         assert!(res);
@@ -121,11 +615,17 @@ mod utests {
         assert_eq!(1, c);
     }
 
+    #[test]
+    #[should_panic(expected = "the number of hash functions must be positive")]
+    fn zero_hashes_() {
+        BloomCounter::<usize>::new(100, 0);
+    }
+
     #[quickcheck]
     fn prop_bloom_filter(xs: Vec<usize>) -> bool {
         let mut filter = BloomCounter::<usize>::new(100000, 10);
 
-        let _: Vec<_> = xs.iter().inspect(|x| filter.insert(x)).collect();
+        let _: Vec<_> = xs.iter().map(|x| filter.insert(x)).collect();
 
         let mut ys = Vec::new();
         ys.extend(xs);
@@ -133,4 +633,194 @@ mod utests {
         ys.iter_mut().all(|x| filter.contains(x));
         true
     }
+
+    #[test]
+    fn many_() {
+        let mut filter = BloomCounter::<usize>::with_capacity_fpr(1000, 0.01);
+        let xs: Vec<usize> = (0..1000).collect();
+        filter.insert_many(&xs).unwrap();
+
+        let ys: Vec<usize> = (500..1500).collect();
+        let expected: Vec<bool> = ys.iter().map(|y| filter.contains(y)).collect();
+        assert_eq!(expected, filter.contains_many(&ys));
+        assert!(filter.contains_many(&xs).into_iter().all(|r| r));
+    }
+
+    #[test]
+    fn bytes_() {
+        let mut filter = BloomCounter::<usize>::with_capacity_fpr(1000, 0.01);
+        (0..1000).for_each(|x| filter.insert(&(x % 100)).unwrap());
+
+        let bytes = filter.to_bytes();
+        let other = BloomCounter::<usize>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(filter.number_of_bits(), other.number_of_bits());
+        assert_eq!(filter.number_of_hashes(), other.number_of_hashes());
+        assert!((0..100).all(|x| other.count(&x) >= 10));
+        assert_eq!(bytes, other.to_bytes());
+    }
+
+    #[test]
+    fn saturate_() {
+        let mut filter = BloomCounter::<usize>::with_capacity_fpr(100, 0.01)
+            .with_counters(CounterWidth::Bits4, OverflowPolicy::Saturate);
+        filter.insert(&7).unwrap();
+
+        (0..20).for_each(|_| filter.insert(&10).unwrap());
+        assert_eq!(15, filter.count(&10));
+        assert!(filter.is_saturated(&10));
+        assert!(filter.saturated_counters() > 0);
+
+        // The frozen counters keep the item, and its neighbors, in the filter.
+        (0..20).for_each(|_| filter.delete(&10));
+        assert!(filter.contains(&10));
+        assert!(filter.contains(&7));
+    }
+
+    #[test]
+    fn delete_frozen_() {
+        let mut filter = BloomCounter::<usize>::with_capacity_fpr_seed(100, 0.01, &[42; 16])
+            .with_counters(CounterWidth::Bits4, OverflowPolicy::Saturate)
+            .with_verification(10);
+        (0..20).for_each(|_| filter.insert(&10).unwrap());
+
+        // Deleting an item whose counters are all frozen changes nothing.
+        (0..25).for_each(|_| filter.delete(&10));
+        assert_eq!(20, filter.len());
+        assert!(filter.contains(&10));
+        assert_eq!(DeleteOutcome::Frozen, filter.try_delete(&10));
+        assert_eq!(20, filter.len());
+    }
+
+    #[test]
+    fn overflow_error_() {
+        let mut filter = BloomCounter::<usize>::with_capacity_fpr(100, 0.01)
+            .with_counters(CounterWidth::Bits4, OverflowPolicy::Error);
+
+        (0..15).for_each(|_| filter.insert(&10).unwrap());
+        let bytes = filter.to_bytes();
+
+        assert_eq!(Err(BloomError::CounterOverflow), filter.insert(&10));
+        assert_eq!(bytes, filter.to_bytes());
+        assert!(!filter.is_saturated(&10));
+
+        (0..15).for_each(|_| filter.delete(&10));
+        assert!(!filter.contains(&10));
+        assert_eq!(0, filter.count(&10));
+    }
+
+    #[test]
+    fn widths_() {
+        for width in [
+            CounterWidth::Bits4,
+            CounterWidth::Bits8,
+            CounterWidth::Bits16,
+            CounterWidth::Bits32,
+        ] {
+            let mut filter = BloomCounter::<usize>::with_capacity_fpr(1000, 0.01)
+                .with_counters(width, OverflowPolicy::Error);
+            (0..1000).for_each(|x| filter.insert(&(x % 500)).unwrap());
+
+            assert_eq!(width, filter.counter_width());
+            assert!((0..500).all(|x| filter.count(&x) >= 2));
+
+            (0..1000).for_each(|x| filter.delete(&(x % 500)));
+            assert!((0..500).all(|x| filter.count(&x) == 0));
+        }
+    }
+
+    #[quickcheck]
+    fn prop_conservative_never_undercounts(xs: Vec<u8>) -> bool {
+        let mut filter = BloomCounter::<u8>::with_seed(64, 3, &[42; 16])
+            .with_update_mode(UpdateMode::Conservative);
+        xs.iter().for_each(|x| filter.insert(x).unwrap());
+
+        xs.iter()
+            .all(|x| filter.count(x) >= xs.iter().filter(|y| *y == x).count() as u32)
+    }
+
+    #[test]
+    fn try_delete_() {
+        let mut filter = BloomCounter::<usize>::with_capacity_fpr_seed(100, 0.1, &[42; 16])
+            .with_verification(100);
+        (0..100).for_each(|x| filter.insert(&x).unwrap());
+
+        assert_eq!(DeleteOutcome::Removed, filter.try_delete(&10));
+        assert_eq!(DeleteOutcome::NotPresent, filter.try_delete(&10));
+        assert_eq!(99, filter.len());
+
+        // A false positive is reported and does not change the counters.
+        let fp = (100..).find(|x| filter.contains(x)).unwrap();
+        let bytes = filter.to_bytes();
+        assert_eq!(DeleteOutcome::PossiblyFalsePositive, filter.try_delete(&fp));
+        assert_eq!(bytes, filter.to_bytes());
+        assert!((0..100).filter(|&x| x != 10).all(|x| filter.contains(&x)));
+    }
+
+    #[test]
+    fn try_delete_evicted_() {
+        let mut filter = BloomCounter::<usize>::with_capacity_fpr(1000, 0.01).with_verification(10);
+        (0..20).for_each(|x| filter.insert(&x).unwrap());
+
+        assert_eq!(DeleteOutcome::PossiblyFalsePositive, filter.try_delete(&0));
+        assert_eq!(DeleteOutcome::Removed, filter.try_delete(&19));
+
+        // Without a side table nothing can be verified.
+        let mut filter = BloomCounter::<usize>::with_capacity_fpr(1000, 0.01);
+        filter.insert(&1).unwrap();
+        assert_eq!(DeleteOutcome::PossiblyFalsePositive, filter.try_delete(&1));
+        assert!(filter.contains(&1));
+    }
+
+    #[test]
+    fn bytes_width_() {
+        let mut filter = BloomCounter::<usize>::with_capacity_fpr(1000, 0.01)
+            .with_counters(CounterWidth::Bits4, OverflowPolicy::Error);
+        (0..1000).for_each(|x| filter.insert(&(x % 500)).unwrap());
+
+        let bytes = filter.to_bytes();
+        let other = BloomCounter::<usize>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(CounterWidth::Bits4, other.counter_width());
+        assert_eq!(OverflowPolicy::Error, other.overflow_policy());
+        assert!((0..500).all(|x| other.count(&x) >= 2));
+        assert_eq!(bytes, other.to_bytes());
+    }
+
+    #[test]
+    fn bytes_wrong_kind_() {
+        let filter = BloomFilter::<usize>::new(100, 10);
+        let bytes = filter.to_bytes();
+
+        assert!(matches!(
+            BloomCounter::<usize>::from_bytes(&bytes),
+            Err(CodecError::KindMismatch { .. })
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_() {
+        let mut filter = BloomCounter::<usize>::with_capacity_fpr(100, 0.01);
+        (0..100).for_each(|x| filter.insert(&x).unwrap());
+
+        let json = serde_json::to_string(&filter).unwrap();
+        let other: BloomCounter<usize> = serde_json::from_str(&json).unwrap();
+
+        assert!((0..100).all(|x| other.contains(&x)));
+        assert_eq!(filter.to_bytes(), other.to_bytes());
+    }
+
+    #[test]
+    fn seed_() {
+        let seed = [42u8; 16];
+
+        let mut filter1 = BloomCounter::<usize>::with_capacity_fpr_seed(1000, 0.01, &seed);
+        let mut filter2 = BloomCounter::<usize>::with_capacity_fpr_seed(1000, 0.01, &seed);
+        assert_eq!(seed, filter1.seed());
+
+        (0..1000).for_each(|x| filter1.insert(&x).unwrap());
+        (0..1000).rev().for_each(|x| filter2.insert(&x).unwrap());
+        assert_eq!(filter1.to_bytes(), filter2.to_bytes());
+    }
 }
@@ -0,0 +1,35 @@
+use std::{error::Error, fmt::Display};
+
+/// The errors returned by the Bloom filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BloomError {
+    /// The filters have a different number of bits.
+    BitsMismatch { expected: usize, found: usize },
+    /// The filters have a different number of hash functions.
+    HashesMismatch { expected: usize, found: usize },
+    /// The filters hashers were created with different seeds.
+    SeedMismatch,
+    /// A counter would go past its largest value.
+    CounterOverflow,
+}
+
+impl Display for BloomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BitsMismatch { expected, found } => {
+                write!(
+                    f,
+                    "expected a filter with {expected} bits, found {found} bits"
+                )
+            }
+            Self::HashesMismatch { expected, found } => write!(
+                f,
+                "expected a filter with {expected} hash functions, found {found} hash functions"
+            ),
+            Self::SeedMismatch => write!(f, "the filters have different hasher seeds"),
+            Self::CounterOverflow => write!(f, "a counter would overflow"),
+        }
+    }
+}
+
+impl Error for BloomError {}
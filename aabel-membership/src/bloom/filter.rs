@@ -1,14 +1,19 @@
-use aabel_hash::hash::{Hash128Ext, Hasher128, HasherExt, SipHasher24};
+use aabel_codec::{CodecError, Header, SketchKind};
+use aabel_hash::hash::{Hash128Ext, Hasher128, HasherExt, HasherId, SipHasher24};
 use bit_vec::BitVec;
 use std::{
     hash::{Hash, Hasher},
     marker::PhantomData,
 };
 
-use super::compute_optimal;
+use crate::prefetch::{prefetch, BATCH};
+
+use super::{compute_optimal, BloomError};
 
 pub struct BloomFilter<T, H = SipHasher24> {
     bits: BitVec,
+    /// Number of inserted items.
+    len: usize,
     // Number of bits
     m: usize,
     /// Number of hash functions.
@@ -23,17 +28,25 @@ where
     T: Hash,
 {
     /// Creates a `BloomFilter` with *m* number of bits and *k* number of hash functions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if *k* is 0.
     pub fn new(m: usize, k: usize) -> Self {
-        let bits = BitVec::from_elem(m, false);
         let hasher = <H as HasherExt>::with_rnd_seed();
+        Self::with_hasher(m, k, hasher)
+    }
 
-        Self {
-            m,
-            k,
-            bits,
-            hasher,
-            _p: PhantomData,
-        }
+    /// Creates a `BloomFilter` with *m* number of bits and *k* number of hash functions,
+    /// with the hasher seeded from a given seed. Filters created with the same seed
+    /// and dimensions are bit-compatible.
+    ///
+    /// # Panics
+    ///
+    /// Panics if *k* is 0.
+    pub fn with_seed(m: usize, k: usize, seed: &[u8; 16]) -> Self {
+        let hasher = <H as HasherExt>::with_seed(seed);
+        Self::with_hasher(m, k, hasher)
     }
 
     /// Creates a new Bloom filter which is expected to store a given number of
@@ -43,6 +56,33 @@ where
         Self::new(m, k)
     }
 
+    /// Creates a new Bloom filter which is expected to store a given number of
+    /// elements and with an expected false positive rate, with the hasher seeded
+    /// from a given seed.
+    pub fn with_capacity_fpr_seed(
+        num_items: usize,
+        false_positive_rate: f64,
+        seed: &[u8; 16],
+    ) -> Self {
+        let (m, k) = compute_optimal(num_items, false_positive_rate);
+        Self::with_seed(m, k, seed)
+    }
+
+    fn with_hasher(m: usize, k: usize, hasher: H) -> Self {
+        assert!(k > 0, "the number of hash functions must be positive");
+
+        let bits = BitVec::from_elem(m, false);
+
+        Self {
+            m,
+            k,
+            bits,
+            len: 0,
+            hasher,
+            _p: PhantomData,
+        }
+    }
+
     /// Returns the number of bits in the filter
     pub fn number_of_bits(&self) -> usize {
         self.m
@@ -53,31 +93,345 @@ where
         self.k
     }
 
-    /// Returns the bits in the filter.
-    pub fn to_bytes(&self) -> Vec<u8> {
-        self.bits.to_bytes()
+    /// Returns the seed of the hasher.
+    pub fn seed(&self) -> [u8; 16] {
+        self.hasher.seed()
+    }
+
+    /// Returns the number of items inserted in the filter.
+    ///
+    /// After a union or an intersection the exact number is unknown and the
+    /// estimated number of items is used instead.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Determines if no item was inserted in the filter.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
     /// Inserts a new item in the filter.
     pub fn insert(&mut self, item: &T) {
-        self.get_indexes(item)
-            .iter()
-            .for_each(|&idx| self.bits.set(idx, true))
+        for idx in self.get_indexes(item) {
+            self.bits.set(idx, true);
+        }
+
+        self.len += 1;
     }
 
     /// Determines if an item belongs to the filter.
     pub fn contains(&self, item: &T) -> bool {
         self.get_indexes(item)
+            .all(|idx| self.bits.get(idx).unwrap())
+    }
+
+    /// Inserts a batch of items in the filter.
+    ///
+    /// The items are hashed ahead of setting their bits, so the memory loads
+    /// of several items overlap.
+    pub fn insert_many(&mut self, items: &[T]) {
+        let mut idxs = Vec::with_capacity(BATCH * self.k);
+
+        for chunk in items.chunks(BATCH) {
+            self.batch_indexes(chunk, &mut idxs);
+            for &idx in &idxs {
+                self.bits.set(idx, true);
+            }
+        }
+
+        self.len += items.len();
+    }
+
+    /// Determines for each item of a batch if it belongs to the filter.
+    pub fn contains_many(&self, items: &[T]) -> Vec<bool> {
+        let mut res = Vec::with_capacity(items.len());
+        let mut idxs = Vec::with_capacity(BATCH * self.k);
+
+        for chunk in items.chunks(BATCH) {
+            self.batch_indexes(chunk, &mut idxs);
+            res.extend((0..chunk.len()).map(|i| {
+                idxs[i * self.k..(i + 1) * self.k]
+                    .iter()
+                    .all(|&idx| self.bits.get(idx).unwrap())
+            }));
+        }
+
+        res
+    }
+
+    /// Returns the number of bits set in the filter.
+    pub fn number_of_set_bits(&self) -> usize {
+        self.bits
+            .storage()
             .iter()
-            .all(|&idx| self.bits.get(idx).unwrap())
+            .map(|w| w.count_ones() as usize)
+            .sum()
     }
 
-    /// Returns the indexes that represent a givenitem
-    fn get_indexes(&self, item: &T) -> Vec<usize> {
-        item.get_hashes(self.k, self.hasher)
+    /// Returns the estimated number of items inserted in the filter, computed
+    /// from the number of set bits with the Swamidass-Baldi formula.
+    pub fn estimate_len(&self) -> f64 {
+        estimate_len(self.m, self.k, self.number_of_set_bits())
+    }
+
+    /// Returns the ratio of bits set in the filter.
+    pub fn fill_ratio(&self) -> f64 {
+        self.number_of_set_bits() as f64 / self.m as f64
+    }
+
+    /// Returns the current false positive rate, the probability that all the
+    /// bits of an item which was not inserted are set.
+    pub fn current_fpr(&self) -> f64 {
+        self.fill_ratio().powi(self.k as i32)
+    }
+
+    /// Determines if the current false positive rate reached a target rate,
+    /// in which case the filter should be replaced by a larger one.
+    pub fn is_saturated(&self, target_fpr: f64) -> bool {
+        self.current_fpr() >= target_fpr
+    }
+
+    /// Returns a new filter which contains the items of both filters.
+    pub fn union(&self, other: &Self) -> Result<Self, BloomError> {
+        let mut filter = self.clone_filter();
+        filter.union_with(other)?;
+        Ok(filter)
+    }
+
+    /// Returns a new filter which contains the items present in both filters.
+    ///
+    /// The false positive rate of the result is at least as high as the one of
+    /// a filter built only from the common items.
+    pub fn intersect(&self, other: &Self) -> Result<Self, BloomError> {
+        let mut filter = self.clone_filter();
+        filter.intersect_with(other)?;
+        Ok(filter)
+    }
+
+    /// Adds the items of another filter to this filter.
+    pub fn union_with(&mut self, other: &Self) -> Result<(), BloomError> {
+        self.check_compatible(other)?;
+        self.bits.or(&other.bits);
+        // A saturated filter estimates an infinite length.
+        self.len = (self.estimate_len().round() as usize).min(self.len.saturating_add(other.len));
+        Ok(())
+    }
+
+    /// Keeps in this filter only the items which are present in another filter.
+    pub fn intersect_with(&mut self, other: &Self) -> Result<(), BloomError> {
+        self.check_compatible(other)?;
+        self.bits.and(&other.bits);
+        self.len = (self.estimate_len().round() as usize).min(self.len.min(other.len));
+        Ok(())
+    }
+
+    /// Determines if the items of this filter may all be part of another filter.
+    ///
+    /// A `false` result is definitive, a `true` result is subject to false positives.
+    pub fn is_subset(&self, other: &Self) -> Result<bool, BloomError> {
+        self.check_compatible(other)?;
+
+        let subset = self
+            .bits
+            .storage()
+            .iter()
+            .zip(other.bits.storage())
+            .all(|(a, b)| a & !b == 0);
+        Ok(subset)
+    }
+
+    /// Returns the estimated number of items in the union of the two filters.
+    pub fn estimate_union_len(&self, other: &Self) -> Result<f64, BloomError> {
+        self.check_compatible(other)?;
+
+        let set_bits = self
+            .bits
+            .storage()
             .iter()
-            .map(|h| (*h as usize) % self.m)
-            .collect()
+            .zip(other.bits.storage())
+            .map(|(a, b)| (a | b).count_ones() as usize)
+            .sum();
+        Ok(estimate_len(self.m, self.k, set_bits))
+    }
+
+    /// Returns the estimated number of items in the intersection of the two filters,
+    /// by the inclusion-exclusion of the estimated lengths of each filter and their union.
+    pub fn estimate_intersection_len(&self, other: &Self) -> Result<f64, BloomError> {
+        let union = self.estimate_union_len(other)?;
+        let intersection = self.estimate_len() + other.estimate_len() - union;
+        Ok(intersection.max(0.0))
+    }
+
+    fn check_compatible(&self, other: &Self) -> Result<(), BloomError> {
+        if self.m != other.m {
+            return Err(BloomError::BitsMismatch {
+                expected: self.m,
+                found: other.m,
+            });
+        }
+
+        if self.k != other.k {
+            return Err(BloomError::HashesMismatch {
+                expected: self.k,
+                found: other.k,
+            });
+        }
+
+        if self.hasher.seed() != other.hasher.seed() {
+            return Err(BloomError::SeedMismatch);
+        }
+
+        Ok(())
+    }
+
+    fn clone_filter(&self) -> Self {
+        Self {
+            bits: self.bits.clone(),
+            len: self.len,
+            m: self.m,
+            k: self.k,
+            hasher: self.hasher,
+            _p: PhantomData,
+        }
+    }
+
+    /// Collects the indexes of a batch of items and prefetches their words.
+    fn batch_indexes(&self, items: &[T], idxs: &mut Vec<usize>) {
+        idxs.clear();
+
+        for item in items {
+            for idx in self.get_indexes(item) {
+                prefetch(self.bits.storage(), idx / 32);
+                idxs.push(idx);
+            }
+        }
+    }
+
+    /// Returns the indexes that represent a givenitem
+    fn get_indexes(&self, item: &T) -> impl Iterator<Item = usize> {
+        let m = self.m;
+        item.probes(self.k, self.hasher)
+            .map(move |h| (h as usize) % m)
+    }
+}
+
+/// Estimates the number of items from the number of set bits (Swamidass & Baldi).
+fn estimate_len(m: usize, k: usize, set_bits: usize) -> f64 {
+    let (m, k, x) = (m as f64, k as f64, set_bits as f64);
+    -(m / k) * (1.0 - x / m).ln()
+}
+
+impl<T, H> BloomFilter<T, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt + HasherId,
+    T: Hash,
+{
+    /// Encodes the filter, its parameters and the hasher seed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let params = vec![self.m as u64, self.k as u64, self.len as u64];
+        let header = Header::new(
+            SketchKind::BloomFilter,
+            H::HASHER_ID,
+            self.hasher.seed(),
+            params,
+        );
+        header.encode(&self.bits.to_bytes())
+    }
+
+    /// Decodes a filter encoded with [`BloomFilter::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (header, payload) = Header::decode(bytes)?;
+        header.expect_kind(SketchKind::BloomFilter)?;
+        header.expect_hasher(H::HASHER_ID)?;
+
+        let [m, k, len] = header.expect_params()?;
+        let (m, k, len) = (m as usize, k as usize, len as usize);
+        if m == 0 || k == 0 {
+            return Err(CodecError::InvalidParameters);
+        }
+
+        if payload.len() != m.div_ceil(8) {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        let mut bits = BitVec::from_bytes(payload);
+        bits.truncate(m);
+
+        Ok(Self {
+            bits,
+            len,
+            m,
+            k,
+            hasher: H::with_seed(&header.seed),
+            _p: PhantomData,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use std::marker::PhantomData;
+
+    use aabel_hash::hash::HasherExt;
+    use bit_vec::BitVec;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::BloomFilter;
+
+    #[derive(Serialize)]
+    struct BloomFilterRef<'a> {
+        m: usize,
+        k: usize,
+        len: usize,
+        seed: [u8; 16],
+        bits: &'a BitVec,
+    }
+
+    #[derive(Deserialize)]
+    struct BloomFilterRepr {
+        m: usize,
+        k: usize,
+        len: usize,
+        seed: [u8; 16],
+        bits: BitVec,
+    }
+
+    impl<T, H> Serialize for BloomFilter<T, H>
+    where
+        H: HasherExt,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            BloomFilterRef {
+                m: self.m,
+                k: self.k,
+                len: self.len,
+                seed: self.hasher.seed(),
+                bits: &self.bits,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, T, H> Deserialize<'de> for BloomFilter<T, H>
+    where
+        H: HasherExt,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let repr = BloomFilterRepr::deserialize(deserializer)?;
+            if repr.m == 0 || repr.k == 0 || repr.bits.len() != repr.m {
+                return Err(D::Error::custom("the bits do not match the filter size"));
+            }
+
+            Ok(Self {
+                bits: repr.bits,
+                len: repr.len,
+                m: repr.m,
+                k: repr.k,
+                hasher: H::with_seed(&repr.seed),
+                _p: PhantomData,
+            })
+        }
     }
 }
 
@@ -94,6 +448,12 @@ mod utests {
         assert!(res)
     }
 
+    #[test]
+    #[should_panic(expected = "the number of hash functions must be positive")]
+    fn zero_hashes_() {
+        BloomFilter::<usize>::new(100, 0);
+    }
+
     #[quickcheck]
     fn prop_bloom_filter(xs: Vec<usize>) -> bool {
         let mut filter = BloomFilter::<usize>::new(100000, 10);
@@ -106,4 +466,210 @@ mod utests {
         ys.iter_mut().all(|x| filter.contains(x));
         true
     }
+
+    #[test]
+    fn many_() {
+        let mut filter = BloomFilter::<usize>::with_capacity_fpr(1000, 0.01);
+        let xs: Vec<usize> = (0..1000).collect();
+        filter.insert_many(&xs);
+        assert_eq!(1000, filter.len());
+
+        let ys: Vec<usize> = (500..1500).collect();
+        let expected: Vec<bool> = ys.iter().map(|y| filter.contains(y)).collect();
+        assert_eq!(expected, filter.contains_many(&ys));
+        assert!(filter.contains_many(&xs).into_iter().all(|r| r));
+    }
+
+    #[test]
+    fn bytes_() {
+        let mut filter = BloomFilter::<usize>::with_capacity_fpr(1000, 0.01);
+        (0..1000).for_each(|x| filter.insert(&x));
+
+        let bytes = filter.to_bytes();
+        let other = BloomFilter::<usize>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(filter.number_of_bits(), other.number_of_bits());
+        assert_eq!(filter.number_of_hashes(), other.number_of_hashes());
+        assert!((0..1000).all(|x| other.contains(&x)));
+        assert_eq!(bytes, other.to_bytes());
+    }
+
+    #[test]
+    fn bytes_corrupted_() {
+        let filter = BloomFilter::<usize>::new(100, 10);
+        let mut bytes = filter.to_bytes();
+
+        bytes[30] ^= 0xff;
+        assert!(matches!(
+            BloomFilter::<usize>::from_bytes(&bytes),
+            Err(CodecError::ChecksumMismatch)
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_() {
+        let mut filter = BloomFilter::<usize>::with_capacity_fpr(100, 0.01);
+        (0..100).for_each(|x| filter.insert(&x));
+
+        let json = serde_json::to_string(&filter).unwrap();
+        let other: BloomFilter<usize> = serde_json::from_str(&json).unwrap();
+
+        assert!((0..100).all(|x| other.contains(&x)));
+        assert_eq!(filter.to_bytes(), other.to_bytes());
+    }
+
+    #[test]
+    fn seed_() {
+        let seed = [42u8; 16];
+
+        let mut filter1 = BloomFilter::<usize>::with_capacity_fpr_seed(1000, 0.01, &seed);
+        let mut filter2 = BloomFilter::<usize>::with_capacity_fpr_seed(1000, 0.01, &seed);
+        assert_eq!(seed, filter1.seed());
+
+        (0..1000).for_each(|x| filter1.insert(&x));
+        (0..1000).rev().for_each(|x| filter2.insert(&x));
+        assert_eq!(filter1.to_bytes(), filter2.to_bytes());
+
+        let filter3 = BloomFilter::<usize>::with_seed(100, 10, &[7u8; 16]);
+        assert_ne!(filter1.seed(), filter3.seed());
+    }
+
+    fn filters() -> (BloomFilter<usize>, BloomFilter<usize>) {
+        let seed = [42u8; 16];
+        let mut xs = BloomFilter::<usize>::with_capacity_fpr_seed(1000, 0.01, &seed);
+        let mut ys = BloomFilter::<usize>::with_capacity_fpr_seed(1000, 0.01, &seed);
+
+        (0..600).for_each(|x| xs.insert(&x));
+        (400..1000).for_each(|y| ys.insert(&y));
+
+        (xs, ys)
+    }
+
+    #[test]
+    fn union_() {
+        let (xs, ys) = filters();
+
+        let zs = xs.union(&ys).unwrap();
+        assert!((0..1000).all(|x| zs.contains(&x)));
+
+        let mut ws = xs.union(&ys).unwrap();
+        ws.union_with(&xs).unwrap();
+        assert_eq!(zs.to_bytes(), ws.to_bytes());
+
+        assert!(xs.is_subset(&zs).unwrap());
+        assert!(ys.is_subset(&zs).unwrap());
+        assert!(!zs.is_subset(&xs).unwrap());
+    }
+
+    #[test]
+    fn union_saturated_() {
+        let mut xs = BloomFilter::<usize>::with_seed(64, 3, &[1u8; 16]);
+        let mut ys = BloomFilter::<usize>::with_seed(64, 3, &[1u8; 16]);
+        (0..1000).for_each(|x| xs.insert(&x));
+        (1000..2000).for_each(|x| ys.insert(&x));
+        assert!(xs.estimate_len().is_infinite());
+
+        let zs = xs.union(&ys).unwrap();
+        assert_eq!(2000, zs.len());
+
+        let ws = xs.intersect(&ys).unwrap();
+        assert_eq!(1000, ws.len());
+    }
+
+    #[test]
+    fn intersect_() {
+        let (xs, ys) = filters();
+
+        let zs = xs.intersect(&ys).unwrap();
+        assert!((400..600).all(|x| zs.contains(&x)));
+        assert!(zs.is_subset(&xs).unwrap());
+        assert!(zs.is_subset(&ys).unwrap());
+
+        let mut ws = xs.union(&ys).unwrap();
+        ws.intersect_with(&xs).unwrap();
+        assert!((0..600).all(|x| ws.contains(&x)));
+    }
+
+    #[test]
+    fn estimate_() {
+        let (xs, ys) = filters();
+
+        let n = xs.estimate_len();
+        assert!((n - 600.0).abs() < 30.0, "len={n}");
+
+        let n = xs.estimate_union_len(&ys).unwrap();
+        assert!((n - 1000.0).abs() < 50.0, "union={n}");
+
+        let n = xs.estimate_intersection_len(&ys).unwrap();
+        assert!((n - 200.0).abs() < 50.0, "intersection={n}");
+    }
+
+    #[test]
+    fn incompatible_() {
+        let xs = BloomFilter::<usize>::with_seed(100, 10, &[1u8; 16]);
+
+        let ys = BloomFilter::<usize>::with_seed(200, 10, &[1u8; 16]);
+        assert_eq!(
+            Some(BloomError::BitsMismatch {
+                expected: 100,
+                found: 200
+            }),
+            xs.union(&ys).err()
+        );
+
+        let ys = BloomFilter::<usize>::with_seed(100, 5, &[1u8; 16]);
+        assert_eq!(
+            Some(BloomError::HashesMismatch {
+                expected: 10,
+                found: 5
+            }),
+            xs.intersect(&ys).err()
+        );
+
+        let ys = BloomFilter::<usize>::with_seed(100, 10, &[2u8; 16]);
+        assert_eq!(Some(BloomError::SeedMismatch), xs.is_subset(&ys).err());
+    }
+
+    #[test]
+    fn len_() {
+        let mut filter = BloomFilter::<usize>::with_capacity_fpr(1000, 0.01);
+        assert!(filter.is_empty());
+
+        (0..500).for_each(|x| filter.insert(&x));
+        assert_eq!(500, filter.len());
+        assert_eq!(
+            500,
+            BloomFilter::<usize>::from_bytes(&filter.to_bytes())
+                .unwrap()
+                .len()
+        );
+
+        let (xs, ys) = filters();
+        let zs = xs.union(&ys).unwrap();
+        assert!(zs.len().abs_diff(1000) < 50);
+    }
+
+    #[test]
+    fn fpr_() {
+        let mut filter = BloomFilter::<usize>::with_capacity_fpr(1000, 0.01);
+        assert_eq!(0.0, filter.current_fpr());
+
+        (0..1000).for_each(|x| filter.insert(&x));
+        let fpr = filter.current_fpr();
+        assert!(0.005 < fpr && fpr < 0.02, "fpr={fpr}");
+        assert!(!filter.is_saturated(0.05));
+
+        (1000..3000).for_each(|x| filter.insert(&x));
+        assert!(filter.is_saturated(0.05));
+
+        // The measured false positive rate is close to the reported one.
+        let fpr = filter.current_fpr();
+        let fps = (10_000..110_000).filter(|x| filter.contains(x)).count();
+        let measured = fps as f64 / 100_000.0;
+        assert!(
+            (measured - fpr).abs() < 0.2 * fpr,
+            "fpr={fpr} measured={measured}"
+        );
+    }
 }
@@ -1,8 +1,24 @@
+mod atomic;
+mod blocked;
 mod counter;
+mod error;
 mod filter;
+mod scalable;
+mod spectral;
+mod verify;
+mod width;
 
+pub use atomic::*;
+pub use blocked::*;
 pub use counter::*;
+pub use error::*;
 pub use filter::*;
+pub use scalable::*;
+pub use spectral::*;
+pub use width::{CounterWidth, OverflowPolicy, UpdateMode};
+
+pub(crate) use verify::SideTable;
+pub(crate) use width::PackedCounters;
 
 /// Returns the optimal size of the filter and the number of hash functions.
 pub(crate) fn compute_optimal(items: usize, false_positive_rate: f64) -> (usize, usize) {
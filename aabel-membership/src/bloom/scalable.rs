@@ -0,0 +1,302 @@
+use std::hash::{Hash, Hasher};
+
+use aabel_hash::hash::{Hasher128, HasherExt, SipHasher24};
+
+use super::{compute_optimal, BloomFilter};
+
+/// The default growth factor of the filters capacity.
+const GROWTH: usize = 2;
+
+/// The default tightening ratio of the filters false positive rate.
+const TIGHTENING: f64 = 0.9;
+
+/// A Bloom filter which grows with the number of inserted items, as described
+/// by Almeida et al. in "Scalable Bloom Filters".
+///
+/// The filter chains Bloom filters with geometrically growing capacities. Each
+/// new filter gets a tighter false positive rate, so the compounded rate stays
+/// under the target rate no matter how many items are inserted.
+pub struct ScalableBloomFilter<T, H = SipHasher24> {
+    filters: Vec<BloomFilter<T, H>>,
+    capacities: Vec<usize>,
+    initial_capacity: usize,
+    false_positive_rate: f64,
+    growth: usize,
+    tightening: f64,
+    seed: Option<[u8; 16]>,
+}
+
+impl<T, H> ScalableBloomFilter<T, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt,
+    T: Hash,
+{
+    /// Creates a new filter which starts with a given capacity and keeps the
+    /// false positive rate under a given rate.
+    pub fn new(initial_capacity: usize, false_positive_rate: f64) -> Self {
+        Self::with_growth_tightening(initial_capacity, false_positive_rate, GROWTH, TIGHTENING)
+    }
+
+    /// Creates a new filter which starts with a given capacity and keeps the
+    /// false positive rate under a given rate, with the hashers of the chained
+    /// filters seeded from a given seed.
+    pub fn with_seed(initial_capacity: usize, false_positive_rate: f64, seed: &[u8; 16]) -> Self {
+        Self::with_growth_tightening_seed(
+            initial_capacity,
+            false_positive_rate,
+            GROWTH,
+            TIGHTENING,
+            seed,
+        )
+    }
+
+    /// Creates a new filter where each new filter has `growth` times the capacity
+    /// of the previous one and `tightening` times its false positive rate.
+    pub fn with_growth_tightening(
+        initial_capacity: usize,
+        false_positive_rate: f64,
+        growth: usize,
+        tightening: f64,
+    ) -> Self {
+        Self::build(
+            initial_capacity,
+            false_positive_rate,
+            growth,
+            tightening,
+            None,
+        )
+    }
+
+    /// Creates a new filter where each new filter has `growth` times the capacity
+    /// of the previous one and `tightening` times its false positive rate, with
+    /// the hashers of the chained filters seeded from a given seed.
+    pub fn with_growth_tightening_seed(
+        initial_capacity: usize,
+        false_positive_rate: f64,
+        growth: usize,
+        tightening: f64,
+        seed: &[u8; 16],
+    ) -> Self {
+        Self::build(
+            initial_capacity,
+            false_positive_rate,
+            growth,
+            tightening,
+            Some(*seed),
+        )
+    }
+
+    fn build(
+        initial_capacity: usize,
+        false_positive_rate: f64,
+        growth: usize,
+        tightening: f64,
+        seed: Option<[u8; 16]>,
+    ) -> Self {
+        assert!(
+            initial_capacity > 0,
+            "the initial capacity must be positive"
+        );
+        assert!(
+            0.0 < false_positive_rate && false_positive_rate < 1.0,
+            "the false positive rate must be between 0 and 1"
+        );
+        assert!(growth >= 1, "the growth factor must be at least 1");
+        assert!(
+            0.0 < tightening && tightening < 1.0,
+            "the tightening ratio must be between 0 and 1"
+        );
+
+        let mut filter = Self {
+            filters: vec![],
+            capacities: vec![],
+            initial_capacity,
+            false_positive_rate,
+            growth,
+            tightening,
+            seed,
+        };
+
+        filter.add_filter();
+        filter
+    }
+
+    /// Returns the number of chained filters.
+    pub fn number_of_filters(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns the number of bits of all the chained filters.
+    pub fn number_of_bits(&self) -> usize {
+        self.filters.iter().map(|f| f.number_of_bits()).sum()
+    }
+
+    /// Returns the number of items the chained filters can hold before a new one is added.
+    pub fn capacity(&self) -> usize {
+        self.capacities.iter().sum()
+    }
+
+    /// Returns the number of items inserted in the filter.
+    pub fn len(&self) -> usize {
+        self.filters.iter().map(|f| f.len()).sum()
+    }
+
+    /// Determines if no item was inserted in the filter.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the false positive rate the filter stays under.
+    pub fn false_positive_rate(&self) -> f64 {
+        self.false_positive_rate
+    }
+
+    /// Returns the current false positive rate, compounded over the chained filters.
+    pub fn current_fpr(&self) -> f64 {
+        1.0 - self
+            .filters
+            .iter()
+            .map(|f| 1.0 - f.current_fpr())
+            .product::<f64>()
+    }
+
+    /// Inserts a new item in the filter, unless it is already present.
+    pub fn insert(&mut self, item: &T) {
+        if self.contains(item) {
+            return;
+        }
+
+        let last = self.filters.len() - 1;
+        if self.filters[last].len() >= self.capacities[last] {
+            self.add_filter();
+        }
+
+        self.filters.last_mut().unwrap().insert(item);
+    }
+
+    /// Determines if an item belongs to the filter.
+    pub fn contains(&self, item: &T) -> bool {
+        self.filters.iter().any(|f| f.contains(item))
+    }
+
+    fn add_filter(&mut self) {
+        let i = self.filters.len() as i32;
+
+        // The rates form a geometric series which sums up to the target rate.
+        let fpr = self.false_positive_rate * (1.0 - self.tightening) * self.tightening.powi(i);
+        let capacity = match self.capacities.last() {
+            Some(last) => last.saturating_mul(self.growth),
+            None => self.initial_capacity,
+        };
+
+        let (m, k) = compute_optimal(capacity, fpr);
+        let filter = match &self.seed {
+            Some(seed) => BloomFilter::with_seed(m, k, &filter_seed(seed, i as u64)),
+            None => BloomFilter::new(m, k),
+        };
+
+        self.filters.push(filter);
+        self.capacities.push(capacity);
+    }
+}
+
+/// Returns the seed of the *i*-th chained filter, mixing its index in the
+/// given seed so the filters probe unrelated positions.
+fn filter_seed(seed: &[u8; 16], i: u64) -> [u8; 16] {
+    let mut seed = *seed;
+    let mix = i.wrapping_mul(0x9e37_79b9_7f4a_7c15).to_le_bytes();
+    seed[..8].iter_mut().zip(mix).for_each(|(b, m)| *b ^= m);
+    seed
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+    use std::collections::HashSet;
+
+    #[test]
+    fn simple_() {
+        let mut filter = ScalableBloomFilter::<usize>::new(100, 0.01);
+        assert!(filter.is_empty());
+
+        filter.insert(&10);
+        assert!(filter.contains(&10));
+        assert_eq!(1, filter.len());
+
+        // Present items are not inserted twice.
+        filter.insert(&10);
+        assert_eq!(1, filter.len());
+    }
+
+    #[test]
+    fn grow_() {
+        let mut filter = ScalableBloomFilter::<usize>::new(100, 0.01);
+
+        (0..10_000).for_each(|x| filter.insert(&x));
+        assert!((0..10_000).all(|x| filter.contains(&x)));
+
+        // 100 + 200 + ... + 3200 < 10000 <= 100 + 200 + ... + 6400
+        assert_eq!(7, filter.number_of_filters());
+        assert_eq!(12_700, filter.capacity());
+        assert!(filter.current_fpr() < filter.false_positive_rate());
+
+        let fps = (10_000..110_000).filter(|x| filter.contains(x)).count();
+        let measured = fps as f64 / 100_000.0;
+        assert!(measured < 0.01, "measured={measured}");
+    }
+
+    #[test]
+    fn growth_tightening_() {
+        let mut filter = ScalableBloomFilter::<usize>::with_growth_tightening(100, 0.01, 4, 0.5);
+
+        (0..1000).for_each(|x| filter.insert(&x));
+        assert_eq!(3, filter.number_of_filters());
+        assert_eq!(2100, filter.capacity());
+    }
+
+    #[test]
+    fn seed_() {
+        let mut xs = ScalableBloomFilter::<usize>::with_seed(10, 0.01, &[1u8; 16]);
+        let mut ys = ScalableBloomFilter::<usize>::with_seed(10, 0.01, &[1u8; 16]);
+        (0..100).for_each(|x| xs.insert(&x));
+        (0..100).for_each(|x| ys.insert(&x));
+
+        assert_eq!(xs.number_of_filters(), ys.number_of_filters());
+        assert!(xs
+            .filters
+            .iter()
+            .zip(&ys.filters)
+            .all(|(x, y)| x.to_bytes() == y.to_bytes()));
+
+        // The first filter keeps the seed, the next ones get their own.
+        let seeds: HashSet<_> = xs.filters.iter().map(|f| f.seed()).collect();
+        assert_eq!(xs.number_of_filters(), seeds.len());
+        assert_eq!([1u8; 16], xs.filters[0].seed());
+    }
+
+    #[test]
+    fn growth_tightening_seed_() {
+        let mut filter = ScalableBloomFilter::<usize>::with_growth_tightening_seed(
+            100, 0.01, 4, 0.5, &[1u8; 16],
+        );
+
+        (0..1000).for_each(|x| filter.insert(&x));
+        assert_eq!(3, filter.number_of_filters());
+        assert_eq!(2100, filter.capacity());
+        assert_eq!([1u8; 16], filter.filters[0].seed());
+    }
+
+    #[test]
+    #[should_panic(expected = "the false positive rate must be between 0 and 1")]
+    fn invalid_fpr_() {
+        ScalableBloomFilter::<usize>::new(100, 1.0);
+    }
+
+    #[quickcheck]
+    fn prop_scalable_bloom_filter(xs: Vec<usize>) -> bool {
+        let mut filter = ScalableBloomFilter::<usize>::new(10, 0.01);
+        xs.iter().for_each(|x| filter.insert(x));
+        xs.iter().all(|x| filter.contains(x))
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::{HashMap, VecDeque};
+
+/// An exact table of the most recently inserted items, keyed by their 128 bits
+/// hash, used to verify deletes. Once full, the least recently inserted item is
+/// evicted.
+pub(crate) struct SideTable {
+    capacity: usize,
+    /// The number of insertions and the sequence of the latest one, per item.
+    counts: HashMap<u128, (u32, u64)>,
+    /// The items in insertion order, with stale entries for removed items.
+    order: VecDeque<(u128, u64)>,
+    seq: u64,
+}
+
+impl SideTable {
+    pub(crate) fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "the side table capacity must be positive");
+
+        Self {
+            capacity,
+            counts: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            seq: 0,
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub(crate) fn insert(&mut self, key: u128) {
+        self.seq += 1;
+        let seq = self.seq;
+
+        let entry = self.counts.entry(key).or_insert((0, seq));
+        entry.0 += 1;
+        entry.1 = seq;
+        self.order.push_back((key, seq));
+
+        while self.counts.len() > self.capacity {
+            let Some((key, seq)) = self.order.pop_front() else {
+                break;
+            };
+
+            // Only the latest entry of an item evicts it.
+            if self.counts.get(&key).is_some_and(|&(_, s)| s == seq) {
+                self.counts.remove(&key);
+            }
+        }
+
+        if self.order.len() > 2 * self.capacity {
+            let counts = &self.counts;
+            self.order
+                .retain(|(key, seq)| counts.get(key).is_some_and(|&(_, s)| s == *seq));
+        }
+    }
+
+    /// Removes one insertion of an item, returning false if it is not in the table.
+    pub(crate) fn remove(&mut self, key: u128) -> bool {
+        match self.counts.get_mut(&key) {
+            Some((count, _)) if *count > 1 => {
+                *count -= 1;
+                true
+            }
+            Some(_) => {
+                self.counts.remove(&key);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+
+    #[test]
+    fn evict_() {
+        let mut table = SideTable::new(2);
+        table.insert(1);
+        table.insert(2);
+        table.insert(1);
+        table.insert(3);
+
+        // The least recently inserted item is evicted.
+        assert_eq!(2, table.counts.len());
+        assert!(!table.remove(2));
+        assert!(table.remove(1));
+        assert!(table.remove(1));
+        assert!(!table.remove(1));
+        assert!(table.remove(3));
+    }
+
+    #[test]
+    fn reinsert_() {
+        let mut table = SideTable::new(2);
+        table.insert(1);
+        assert!(table.remove(1));
+        table.insert(2);
+        table.insert(1);
+        table.insert(3);
+
+        // The stale entry of the first insertion does not evict the item.
+        assert!(table.remove(1));
+        assert!(!table.remove(2));
+    }
+}
@@ -0,0 +1,215 @@
+/// The width of the counters of a `BloomCounter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CounterWidth {
+    /// 4 bits counters, packed two per byte.
+    Bits4,
+    /// 8 bits counters.
+    Bits8,
+    /// 16 bits counters.
+    Bits16,
+    /// 32 bits counters.
+    Bits32,
+}
+
+impl CounterWidth {
+    /// Returns the number of bits of a counter.
+    pub fn bits(&self) -> usize {
+        match self {
+            Self::Bits4 => 4,
+            Self::Bits8 => 8,
+            Self::Bits16 => 16,
+            Self::Bits32 => 32,
+        }
+    }
+
+    /// Returns the largest value a counter can hold.
+    pub fn max(&self) -> u32 {
+        match self {
+            Self::Bits32 => u32::MAX,
+            _ => (1 << self.bits()) - 1,
+        }
+    }
+
+    /// Returns the width with a given number of bits.
+    pub fn from_bits(bits: usize) -> Option<Self> {
+        match bits {
+            4 => Some(Self::Bits4),
+            8 => Some(Self::Bits8),
+            16 => Some(Self::Bits16),
+            32 => Some(Self::Bits32),
+            _ => None,
+        }
+    }
+}
+
+/// What a `BloomCounter` does when a counter would go past its largest value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverflowPolicy {
+    /// The counter stays at its largest value and is frozen, deleting an item
+    /// no longer decrements it, since its true value is unknown.
+    Saturate,
+    /// The insertion is rolled back and an error is returned.
+    Error,
+}
+
+impl OverflowPolicy {
+    pub(crate) fn id(&self) -> u64 {
+        match self {
+            Self::Saturate => 0,
+            Self::Error => 1,
+        }
+    }
+
+    pub(crate) fn from_id(id: u64) -> Option<Self> {
+        match id {
+            0 => Some(Self::Saturate),
+            1 => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// How a `BloomCounter` updates the counters of an inserted item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UpdateMode {
+    /// All the counters of the item are incremented.
+    Standard,
+    /// Only the smallest counters of the item are incremented, the minimal
+    /// increase needed for its count to grow by one. Counts of light items are
+    /// less inflated by heavy ones, but deleting items may cause false negatives.
+    Conservative,
+}
+
+impl UpdateMode {
+    pub(crate) fn id(&self) -> u64 {
+        match self {
+            Self::Standard => 0,
+            Self::Conservative => 1,
+        }
+    }
+
+    pub(crate) fn from_id(id: u64) -> Option<Self> {
+        match id {
+            0 => Some(Self::Standard),
+            1 => Some(Self::Conservative),
+            _ => None,
+        }
+    }
+}
+
+/// A vector of counters of a given width, packed in bytes.
+pub(crate) struct PackedCounters {
+    width: CounterWidth,
+    bytes: Vec<u8>,
+}
+
+impl PackedCounters {
+    /// Creates `m` counters set to zero.
+    pub(crate) fn new(m: usize, width: CounterWidth) -> Self {
+        Self {
+            width,
+            bytes: vec![0; Self::size_in_bytes(m, width)],
+        }
+    }
+
+    /// Creates the counters from their packed bytes.
+    pub(crate) fn from_bytes(m: usize, width: CounterWidth, bytes: Vec<u8>) -> Option<Self> {
+        if bytes.len() != Self::size_in_bytes(m, width) {
+            return None;
+        }
+
+        Some(Self { width, bytes })
+    }
+
+    /// Returns the number of bytes needed by `m` counters.
+    pub(crate) fn size_in_bytes(m: usize, width: CounterWidth) -> usize {
+        (m * width.bits()).div_ceil(8)
+    }
+
+    pub(crate) fn width(&self) -> CounterWidth {
+        self.width
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the index of the byte which stores a counter.
+    #[inline]
+    pub(crate) fn byte_index(&self, i: usize) -> usize {
+        i * self.width.bits() / 8
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, i: usize) -> u32 {
+        let b = &self.bytes;
+        match self.width {
+            CounterWidth::Bits4 => ((b[i / 2] >> ((i % 2) * 4)) & 0xf) as u32,
+            CounterWidth::Bits8 => b[i] as u32,
+            CounterWidth::Bits16 => u16::from_le_bytes([b[2 * i], b[2 * i + 1]]) as u32,
+            CounterWidth::Bits32 => u32::from_le_bytes(b[4 * i..4 * i + 4].try_into().unwrap()),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn set(&mut self, i: usize, v: u32) {
+        let b = &mut self.bytes;
+        match self.width {
+            CounterWidth::Bits4 => {
+                let shift = (i % 2) * 4;
+                b[i / 2] = (b[i / 2] & !(0xf << shift)) | ((v as u8 & 0xf) << shift);
+            }
+            CounterWidth::Bits8 => b[i] = v as u8,
+            CounterWidth::Bits16 => b[2 * i..2 * i + 2].copy_from_slice(&(v as u16).to_le_bytes()),
+            CounterWidth::Bits32 => b[4 * i..4 * i + 4].copy_from_slice(&v.to_le_bytes()),
+        }
+    }
+
+    /// Returns the number of counters at their largest value.
+    pub(crate) fn saturated(&self, m: usize) -> usize {
+        let max = self.width.max();
+        (0..m).filter(|&i| self.get(i) == max).count()
+    }
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+
+    #[test]
+    fn get_set_() {
+        for width in [
+            CounterWidth::Bits4,
+            CounterWidth::Bits8,
+            CounterWidth::Bits16,
+            CounterWidth::Bits32,
+        ] {
+            let mut counters = PackedCounters::new(11, width);
+            assert_eq!(
+                PackedCounters::size_in_bytes(11, width),
+                counters.bytes().len()
+            );
+
+            (0..11).for_each(|i| counters.set(i, (i as u32 + 3) % 16));
+            assert!((0..11).all(|i| counters.get(i) == (i as u32 + 3) % 16));
+
+            counters.set(4, width.max());
+            assert_eq!(width.max(), counters.get(4));
+            assert_eq!(5, counters.get(2));
+            assert_eq!(8, counters.get(5));
+        }
+    }
+
+    #[test]
+    fn width_() {
+        assert_eq!(15, CounterWidth::Bits4.max());
+        assert_eq!(255, CounterWidth::Bits8.max());
+        assert_eq!(u16::MAX as u32, CounterWidth::Bits16.max());
+        assert_eq!(u32::MAX, CounterWidth::Bits32.max());
+        assert_eq!(Some(CounterWidth::Bits16), CounterWidth::from_bits(16));
+        assert_eq!(None, CounterWidth::from_bits(12));
+    }
+}
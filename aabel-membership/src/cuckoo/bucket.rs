@@ -1,55 +1,120 @@
 use std::fmt::Debug;
 
-use super::fingerprint::Fingerprint;
+use super::fingerprint::{Fingerprint, MAX_FINGERPRINT_BITS, MIN_FINGERPRINT_BITS};
 
-#[derive(Clone, Copy)]
-pub struct Bucket<const N: usize> {
-    slots: [Option<Fingerprint>; N],
+/// A bucket of *N* slots holding fingerprints of *F* bits, bit-packed in a
+/// 128 bits word, so `N * F` cannot be over 128. A zero slot is empty.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Bucket<const N: usize, const F: usize = 8> {
+    bits: u128,
 }
 
-impl<const N: usize> Default for Bucket<N> {
+impl<const N: usize, const F: usize> Default for Bucket<N, F> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<const N: usize> Debug for Bucket<N> {
+impl<const N: usize, const F: usize> Debug for Bucket<N, F> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        fn tostr(fp: Option<Fingerprint>) -> String {
-            match fp {
+        let slots: Vec<String> = (0..N)
+            .map(|i| match self.get(i) {
                 Some(fp) => format!("{fp:?}"),
                 None => "_".to_owned(),
-            }
-        }
+            })
+            .collect();
 
-        write!(f, "[{}]", tostr(self.slots[0]))
+        write!(f, "[{}]", slots.join(" "))
     }
 }
 
-impl<const N: usize> Bucket<N> {
+impl<const N: usize, const F: usize> Bucket<N, F> {
+    const MASK: u128 = (1 << F) - 1;
+
+    /// The number of bits used by the slots.
+    pub const BITS: usize = N * F;
+
     pub fn new() -> Self {
-        Self { slots: [None; N] }
+        const {
+            assert!(
+                F >= MIN_FINGERPRINT_BITS && F <= MAX_FINGERPRINT_BITS,
+                "the fingerprints must have between 4 and 32 bits"
+            );
+            assert!(N > 0 && N * F <= 128, "the slots must fit in 128 bits");
+        }
+
+        Self { bits: 0 }
+    }
+
+    /// Creates a bucket from its packed slots, or none if bits past the last
+    /// slot are set.
+    pub fn from_bits(bits: u128) -> Option<Self> {
+        let mut bucket = Self::new();
+        if Self::BITS < 128 && bits >> Self::BITS != 0 {
+            return None;
+        }
+
+        bucket.bits = bits;
+        Some(bucket)
+    }
+
+    /// Returns the packed slots.
+    pub fn bits(&self) -> u128 {
+        self.bits
+    }
+
+    /// Returns the fingerprint stored in a slot.
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<Fingerprint> {
+        Fingerprint::from_slot(((self.bits >> (idx * F)) & Self::MASK) as u32)
+    }
+
+    /// Stores a fingerprint in a slot, or empties the slot.
+    #[inline]
+    pub fn set(&mut self, idx: usize, fp: Option<Fingerprint>) {
+        let value = fp.map_or(0, |fp| *fp.as_ref()) as u128;
+        let shift = idx * F;
+        self.bits = (self.bits & !(Self::MASK << shift)) | (value << shift);
     }
 
     pub fn insert(&mut self, fp: Fingerprint) -> bool {
-        for entry in &mut self.slots {
-            if entry.is_none() {
-                *entry = Some(fp);
-                return true;
+        match (0..N).find(|&i| self.get(i).is_none()) {
+            Some(i) => {
+                self.set(i, Some(fp));
+                true
             }
+            None => false,
         }
+    }
 
-        false
+    /// Removes one occurrence of a fingerprint, returning false if it is not in the bucket.
+    pub fn remove(&mut self, fp: Fingerprint) -> bool {
+        match self.contains(fp) {
+            Some(i) => {
+                self.set(i, None);
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn contains(self, fp: Fingerprint) -> Option<usize> {
-        self.slots.iter().position(|s| *s == Some(fp))
+        (0..N).find(|&i| self.get(i) == Some(fp))
+    }
+
+    /// Returns the number of slots holding a fingerprint.
+    pub fn count(self, fp: Fingerprint) -> usize {
+        (0..N).filter(|&i| self.get(i) == Some(fp)).count()
+    }
+
+    /// Returns the number of slots holding any fingerprint.
+    pub fn used(self) -> usize {
+        (0..N).filter(|&i| self.get(i).is_some()).count()
     }
 
     pub fn swap(&mut self, idx: usize, other: Fingerprint) -> Fingerprint {
-        let loc = &mut self.slots[idx];
-        let old = loc.unwrap();
-        *loc = Some(other);
+        let old = self.get(idx).unwrap();
+        self.set(idx, Some(other));
         old
     }
 }
@@ -62,7 +127,7 @@ mod utests {
     fn debug_() {
         let mut bucket = Bucket::<1>::new();
 
-        let fp1 = Fingerprint::from(1);
+        let fp1 = Fingerprint::new(1, 8);
         let _ = bucket.insert(fp1);
 
         println!("BUCKET_DBG: {:?}", bucket);
@@ -72,16 +137,58 @@ mod utests {
     fn insert_() {
         let mut bucket = Bucket::<2>::new();
 
-        let fp1 = Fingerprint::from(1);
+        let fp1 = Fingerprint::new(1, 8);
         let r = bucket.insert(fp1);
         assert!(r);
 
-        let fp2 = Fingerprint::from(2);
+        let fp2 = Fingerprint::new(2, 8);
         let r = bucket.insert(fp2);
         assert!(r);
 
-        let fp3 = Fingerprint::from(3);
+        let fp3 = Fingerprint::new(3, 8);
         let r = bucket.insert(fp3);
         assert!(!r);
     }
+
+    #[test]
+    fn remove_() {
+        let mut bucket = Bucket::<2>::new();
+
+        let fp1 = Fingerprint::new(1, 8);
+        let _ = bucket.insert(fp1);
+        let _ = bucket.insert(fp1);
+
+        assert_eq!(2, bucket.count(fp1));
+        assert!(bucket.remove(fp1));
+        assert!(bucket.contains(fp1).is_some());
+        assert_eq!(1, bucket.count(fp1));
+        assert!(bucket.remove(fp1));
+        assert!(!bucket.remove(fp1));
+        assert_eq!(0, bucket.used());
+
+        // The freed slots can be reused.
+        assert!(bucket.insert(Fingerprint::new(2, 8)));
+        assert!(bucket.insert(Fingerprint::new(3, 8)));
+    }
+
+    #[test]
+    fn packed_() {
+        let mut bucket = Bucket::<4, 32>::new();
+        let fps: Vec<_> = [7, u32::MAX - 1, 1, 12345]
+            .into_iter()
+            .map(|h| Fingerprint::new(h, 32))
+            .collect();
+        fps.iter().for_each(|fp| assert!(bucket.insert(*fp)));
+
+        assert!((0..4).all(|i| bucket.get(i) == Some(fps[i])));
+        assert_eq!(Some(bucket), Bucket::<4, 32>::from_bits(bucket.bits()));
+
+        let mut bucket = Bucket::<3, 12>::new();
+        let fp = Fingerprint::new(4000, 12);
+        bucket.set(1, Some(fp));
+        assert_eq!(None, bucket.get(0));
+        assert_eq!(Some(fp), bucket.get(1));
+        assert_eq!(None, bucket.get(2));
+        assert_eq!(None, Bucket::<3, 12>::from_bits(1 << 36));
+    }
 }
@@ -0,0 +1,603 @@
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
+
+use aabel_codec::{CodecError, Header, SketchKind};
+use aabel_hash::hash::{Hasher128, HasherExt, HasherId, SipHasher24};
+use log::debug;
+use rand::Rng;
+
+use super::{
+    fi_pair::FIPair,
+    fingerprint::{Fingerprint, MAX_FINGERPRINT_BITS, MIN_FINGERPRINT_BITS},
+    CuckooError, DEFAULT_MAX_KICKS,
+};
+
+/// The largest number of bits of a counter.
+pub const MAX_COUNTER_BITS: usize = 16;
+
+/// A bucket of *N* slots holding a fingerprint of *F* bits and a counter of
+/// *C* bits each, bit-packed in a 128 bits word, so `N * (F + C)` cannot be
+/// over 128. A slot with a zero fingerprint is empty, the counter of a used
+/// slot is never zero.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CountingBucket<const N: usize, const F: usize = 8, const C: usize = 4> {
+    bits: u128,
+}
+
+impl<const N: usize, const F: usize, const C: usize> Default for CountingBucket<N, F, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const F: usize, const C: usize> Debug for CountingBucket<N, F, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let slots: Vec<String> = (0..N)
+            .map(|i| match self.get(i) {
+                Some((fp, count)) => format!("{fp:?}x{count}"),
+                None => "_".to_owned(),
+            })
+            .collect();
+
+        write!(f, "[{}]", slots.join(" "))
+    }
+}
+
+impl<const N: usize, const F: usize, const C: usize> CountingBucket<N, F, C> {
+    /// The number of bits of a slot.
+    const WIDTH: usize = F + C;
+
+    const FP_MASK: u128 = (1 << F) - 1;
+
+    const SLOT_MASK: u128 = (1 << Self::WIDTH) - 1;
+
+    /// The largest value of a counter.
+    pub const MAX_COUNT: u32 = (1 << C) - 1;
+
+    /// The number of bits used by the slots.
+    pub const BITS: usize = N * Self::WIDTH;
+
+    pub fn new() -> Self {
+        const {
+            assert!(
+                F >= MIN_FINGERPRINT_BITS && F <= MAX_FINGERPRINT_BITS,
+                "the fingerprints must have between 4 and 32 bits"
+            );
+            assert!(
+                C > 0 && C <= MAX_COUNTER_BITS,
+                "the counters must have between 1 and 16 bits"
+            );
+            assert!(
+                N > 0 && N * (F + C) <= 128,
+                "the slots must fit in 128 bits"
+            );
+        }
+
+        Self { bits: 0 }
+    }
+
+    /// Creates a bucket from its packed slots, or none if bits past the last
+    /// slot are set or a slot has a fingerprint without a count.
+    pub fn from_bits(bits: u128) -> Option<Self> {
+        let mut bucket = Self::new();
+        if Self::BITS < 128 && bits >> Self::BITS != 0 {
+            return None;
+        }
+
+        bucket.bits = bits;
+        (0..N)
+            .all(|i| {
+                let slot = bucket.slot(i);
+                slot == 0 || (slot & Self::FP_MASK != 0 && slot >> F != 0)
+            })
+            .then_some(bucket)
+    }
+
+    /// Returns the packed slots.
+    pub fn bits(&self) -> u128 {
+        self.bits
+    }
+
+    #[inline]
+    fn slot(&self, idx: usize) -> u128 {
+        (self.bits >> (idx * Self::WIDTH)) & Self::SLOT_MASK
+    }
+
+    #[inline]
+    fn set_slot(&mut self, idx: usize, slot: u128) {
+        let shift = idx * Self::WIDTH;
+        self.bits = (self.bits & !(Self::SLOT_MASK << shift)) | (slot << shift);
+    }
+
+    /// Returns the fingerprint and the counter stored in a slot.
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<(Fingerprint, u32)> {
+        let slot = self.slot(idx);
+        Fingerprint::from_slot((slot & Self::FP_MASK) as u32).map(|fp| (fp, (slot >> F) as u32))
+    }
+
+    /// Stores a fingerprint and its counter in a slot, or empties the slot.
+    #[inline]
+    pub fn set(&mut self, idx: usize, entry: Option<(Fingerprint, u32)>) {
+        let slot = entry.map_or(0, |(fp, count)| *fp.as_ref() as u128 | (count as u128) << F);
+        self.set_slot(idx, slot);
+    }
+
+    /// Stores a fingerprint with a counter in a free slot.
+    pub fn insert(&mut self, fp: Fingerprint, count: u32) -> bool {
+        match (0..N).find(|&i| self.get(i).is_none()) {
+            Some(i) => {
+                self.set(i, Some((fp, count)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Increments the counter of a fingerprint, returning false if the
+    /// fingerprint is not in the bucket or its counters are saturated.
+    pub fn increment(&mut self, fp: Fingerprint) -> bool {
+        let slot =
+            (0..N).find(|&i| matches!(self.get(i), Some((f, c)) if f == fp && c < Self::MAX_COUNT));
+        match slot {
+            Some(i) => {
+                let (_, count) = self.get(i).unwrap();
+                self.set(i, Some((fp, count + 1)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Decrements the counter of a fingerprint, emptying its slot when the
+    /// counter reaches zero, and returns false if it is not in the bucket.
+    pub fn decrement(&mut self, fp: Fingerprint) -> bool {
+        match (0..N).find(|&i| matches!(self.get(i), Some((f, _)) if f == fp)) {
+            Some(i) => {
+                let (_, count) = self.get(i).unwrap();
+                self.set(i, (count > 1).then_some((fp, count - 1)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the sum of the counters of a fingerprint.
+    pub fn count(&self, fp: Fingerprint) -> usize {
+        (0..N)
+            .filter_map(|i| self.get(i))
+            .filter(|(f, _)| *f == fp)
+            .map(|(_, count)| count as usize)
+            .sum()
+    }
+
+    /// Replaces the content of a used slot, returning the previous one.
+    pub fn swap(&mut self, idx: usize, other: (Fingerprint, u32)) -> (Fingerprint, u32) {
+        let old = self.get(idx).unwrap();
+        self.set(idx, Some(other));
+        old
+    }
+}
+
+/// A counting cuckoo filter with *B* buckets of *N* slots, each slot storing a
+/// fingerprint of *F* bits and a counter of *C* bits, between 1 and 16.
+///
+/// It is an approximate multiset: the copies of an item share a slot, whose
+/// counter is incremented by each insertion and decremented by each deletion.
+/// A saturated counter is continued in another slot of the item's buckets, so
+/// an item can be inserted more than `2^C - 1` times. The count of an item is
+/// never below its multiplicity, and is over it only when another item shares
+/// its fingerprint and one of its buckets.
+pub struct CountingCuckooFilter<
+    const B: usize,
+    const N: usize,
+    const F: usize = 8,
+    const C: usize = 4,
+    H = SipHasher24,
+> {
+    buckets: [CountingBucket<N, F, C>; B],
+    len: usize,
+    max_kicks: usize,
+    hasher: H,
+}
+
+impl<const B: usize, const N: usize, const F: usize, const C: usize, H> Debug
+    for CountingCuckooFilter<B, N, F, C, H>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let buckets: Vec<String> = self.buckets.iter().map(|b| format!("{b:?}")).collect();
+        write!(f, "{}", buckets.join(" "))
+    }
+}
+
+impl<const B: usize, const N: usize, const F: usize, const C: usize, H> Default
+    for CountingCuckooFilter<B, N, F, C, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const B: usize, const N: usize, const F: usize, const C: usize, H>
+    CountingCuckooFilter<B, N, F, C, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt,
+{
+    /// Creates a `CountingCuckooFilter` with the hasher seeded from a random seed.
+    pub fn new() -> Self {
+        let hasher = <H as HasherExt>::with_rnd_seed();
+        Self::with_hasher(hasher)
+    }
+
+    /// Creates a `CountingCuckooFilter` with the hasher seeded from a given seed.
+    pub fn with_seed(seed: &[u8; 16]) -> Self {
+        let hasher = <H as HasherExt>::with_seed(seed);
+        Self::with_hasher(hasher)
+    }
+
+    fn with_hasher(hasher: H) -> Self {
+        Self {
+            buckets: [CountingBucket::<N, F, C>::new(); B],
+            len: 0,
+            max_kicks: DEFAULT_MAX_KICKS,
+            hasher,
+        }
+    }
+
+    /// Returns the seed of the hasher.
+    pub fn seed(&self) -> [u8; 16] {
+        self.hasher.seed()
+    }
+
+    /// Sets the maximum number of slots relocated by an insertion before the
+    /// filter is considered full.
+    pub fn with_max_kicks(mut self, max_kicks: usize) -> Self {
+        self.max_kicks = max_kicks;
+        self
+    }
+
+    /// Returns the maximum number of slots relocated by an insertion.
+    pub fn max_kicks(&self) -> usize {
+        self.max_kicks
+    }
+
+    /// Inserts one copy of an item in the filter.
+    ///
+    /// The counter of the item's fingerprint is incremented when found in one
+    /// of its buckets, otherwise a slot is taken as in the `CuckooFilter`,
+    /// relocating slots when both buckets are full and undoing the relocations
+    /// if the filter is full.
+    pub fn insert<T>(&mut self, data: &T) -> Result<(), CuckooError>
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        let alt = fi.alt(B);
+        debug!("INSERT | {:?}", &fi);
+
+        let placed = self.buckets[*fi.idx.as_ref()].increment(fi.fp)
+            || self.buckets[*alt.idx.as_ref()].increment(fi.fp)
+            || self.put(&fi, 1)
+            || self.put(&alt, 1);
+
+        if !placed {
+            self.rearrange(alt)?;
+        }
+
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn contains<T>(&self, data: &T) -> bool
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        let alt = fi.alt(B);
+
+        self.buckets[*fi.idx.as_ref()].count(fi.fp) > 0
+            || self.buckets[*alt.idx.as_ref()].count(fi.fp) > 0
+    }
+
+    /// Returns the sum of the counters of an item's fingerprint in its two
+    /// buckets, an upper bound of the item's multiplicity.
+    pub fn count<T>(&self, data: &T) -> usize
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        let alt = fi.alt(B);
+
+        let (idx, alt_idx) = (*fi.idx.as_ref(), *alt.idx.as_ref());
+        let n = self.buckets[idx].count(fi.fp);
+        if alt_idx == idx {
+            n
+        } else {
+            n + self.buckets[alt_idx].count(fi.fp)
+        }
+    }
+
+    /// Deletes one copy of an item, returning false if the item was not found.
+    ///
+    /// Only inserted items should be deleted, deleting a false positive
+    /// decrements the counter of another item.
+    pub fn delete<T>(&mut self, data: &T) -> bool
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        let alt = fi.alt(B);
+        debug!("DELETE | {:?}", &fi);
+
+        let found = self.buckets[*fi.idx.as_ref()].decrement(fi.fp)
+            || self.buckets[*alt.idx.as_ref()].decrement(fi.fp);
+
+        if found {
+            self.len -= 1;
+        }
+
+        found
+    }
+
+    /// Returns the number of bits of the packed buckets.
+    pub fn number_of_bits(&self) -> usize {
+        B * CountingBucket::<N, F, C>::BITS
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of copies of the inserted items.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    fn rearrange(&mut self, fi: FIPair) -> Result<(), CuckooError> {
+        debug!("REARRANGE | {:?}", &fi);
+
+        let mut rng = rand::thread_rng();
+        let (mut current, mut count) = (fi, 1);
+
+        // The relocated slots, to undo the kicks on failure.
+        let mut kicked = Vec::new();
+
+        for _ in 0..self.max_kicks {
+            let idx = *current.idx.as_ref();
+            let slot = rng.gen_range(0..N);
+            let other = self.buckets[idx].swap(slot, (current.fp, count));
+            kicked.push((idx, slot, other));
+
+            // The counter moves along with its fingerprint.
+            current = FIPair::new(other.0, current.idx).alt(B);
+            count = other.1;
+            if self.put(&current, count) {
+                debug!("SUCCESS | {:?} | KICKS {}", current, kicked.len());
+                return Ok(());
+            }
+        }
+
+        // Put back the relocated slots, dropping the new one.
+        for (idx, slot, other) in kicked.into_iter().rev() {
+            self.buckets[idx].swap(slot, other);
+        }
+
+        debug!("FULL | {} KICKS", self.max_kicks);
+        Err(CuckooError::Full {
+            kicks: self.max_kicks,
+        })
+    }
+
+    fn put(&mut self, fi: &FIPair, count: u32) -> bool {
+        self.buckets[*fi.idx.as_ref()].insert(fi.fp, count)
+    }
+}
+
+impl<const B: usize, const N: usize, const F: usize, const C: usize, H>
+    CountingCuckooFilter<B, N, F, C, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt + HasherId,
+{
+    /// The number of bytes of an encoded bucket.
+    const BUCKET_BYTES: usize = CountingBucket::<N, F, C>::BITS.div_ceil(8);
+
+    /// Encodes the buckets, their dimensions and the hasher identifier.
+    ///
+    /// Each bucket is encoded on the bytes of its packed slots, in little endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let params = vec![B as u64, N as u64, self.len as u64, F as u64, C as u64];
+        let header = Header::new(
+            SketchKind::CountingCuckooFilter,
+            H::HASHER_ID,
+            self.hasher.seed(),
+            params,
+        );
+
+        let payload: Vec<u8> = self
+            .buckets
+            .iter()
+            .flat_map(|b| b.bits().to_le_bytes()[..Self::BUCKET_BYTES].to_vec())
+            .collect();
+        header.encode(&payload)
+    }
+
+    /// Decodes the buckets encoded with [`CountingCuckooFilter::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (header, payload) = Header::decode(bytes)?;
+        header.expect_kind(SketchKind::CountingCuckooFilter)?;
+        header.expect_hasher(H::HASHER_ID)?;
+
+        let [b, n, len, f, c] = header.expect_params()?;
+        if b != B as u64 || n != N as u64 || f != F as u64 || c != C as u64 {
+            return Err(CodecError::InvalidParameters);
+        }
+
+        if payload.len() != B * Self::BUCKET_BYTES {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        let mut filter = Self::with_seed(&header.seed);
+        let chunks = payload.chunks_exact(Self::BUCKET_BYTES);
+        for (bucket, bytes) in filter.buckets.iter_mut().zip(chunks) {
+            let mut bits = [0; 16];
+            bits[..bytes.len()].copy_from_slice(bytes);
+            *bucket = CountingBucket::from_bits(u128::from_le_bytes(bits))
+                .ok_or(CodecError::InvalidPayload)?;
+        }
+
+        // The length is the sum of the counters.
+        let total: u64 = filter
+            .buckets
+            .iter()
+            .flat_map(|b| (0..N).filter_map(|i| b.get(i)))
+            .map(|(_, count)| count as u64)
+            .sum();
+        if total != len {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        filter.len = len as usize;
+        Ok(filter)
+    }
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+    use std::collections::HashMap;
+
+    const SEED: [u8; 16] = [42; 16];
+
+    #[test]
+    fn bucket_() {
+        let mut bucket = CountingBucket::<2, 8, 2>::new();
+        let fp1 = Fingerprint::new(1, 8);
+        let fp2 = Fingerprint::new(2, 8);
+
+        assert!(!bucket.increment(fp1));
+        assert!(bucket.insert(fp1, 1));
+        assert!(bucket.increment(fp1));
+        assert!(bucket.increment(fp1));
+        assert_eq!(Some((fp1, 3)), bucket.get(0));
+
+        // The counter is saturated.
+        assert!(!bucket.increment(fp1));
+        assert!(bucket.insert(fp2, 1));
+        assert!(!bucket.insert(fp1, 1));
+        assert_eq!(3, bucket.count(fp1));
+
+        assert!(bucket.decrement(fp2));
+        assert_eq!(None, bucket.get(1));
+        assert!(!bucket.decrement(fp2));
+        assert_eq!(Some(bucket), CountingBucket::from_bits(bucket.bits()));
+
+        // A fingerprint without a count is not a valid slot.
+        assert_eq!(None, CountingBucket::<2, 8, 2>::from_bits(1));
+        assert_eq!(None, CountingBucket::<2, 8, 2>::from_bits(1 << 8));
+        assert_eq!(None, CountingBucket::<2, 8, 2>::from_bits(1 << 20));
+    }
+
+    #[test]
+    fn simple_() {
+        let mut filter = CountingCuckooFilter::<12, 2>::with_seed(&SEED);
+        assert_eq!(0, filter.count(&"AAAA"));
+
+        (0..5).for_each(|_| filter.insert(&"AAAA").unwrap());
+        filter.insert(&"BBBB").unwrap();
+        assert_eq!(5, filter.count(&"AAAA"));
+        assert_eq!(1, filter.count(&"BBBB"));
+        assert_eq!(6, filter.len());
+
+        assert!(filter.delete(&"AAAA"));
+        assert_eq!(4, filter.count(&"AAAA"));
+        assert!(filter.delete(&"BBBB"));
+        assert!(!filter.contains(&"BBBB"));
+        assert!(!filter.delete(&"BBBB"));
+        assert_eq!(4, filter.len());
+    }
+
+    #[test]
+    fn saturate_() {
+        // With 2 bits counters, the copies spill over several slots.
+        let mut filter = CountingCuckooFilter::<12, 4, 8, 2>::with_seed(&SEED);
+        (0..10).for_each(|_| filter.insert(&"AAAA").unwrap());
+        assert_eq!(10, filter.count(&"AAAA"));
+
+        (0..10).for_each(|_| assert!(filter.delete(&"AAAA")));
+        assert!(!filter.contains(&"AAAA"));
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn full_() {
+        let mut filter = CountingCuckooFilter::<8, 2>::new().with_max_kicks(50);
+
+        // A failed insertion must not lose any count.
+        let mut inserted = Vec::new();
+        for x in 0..100usize {
+            match filter.insert(&x) {
+                Ok(()) => inserted.push(x),
+                Err(CuckooError::Full { kicks }) => assert_eq!(50, kicks),
+            }
+        }
+
+        assert_eq!(inserted.len(), filter.len());
+        assert!(inserted.iter().all(|x| filter.count(x) >= 1));
+    }
+
+    #[test]
+    fn bytes_() {
+        let mut filter = CountingCuckooFilter::<16, 3, 12, 6>::with_seed(&SEED);
+        (0..60usize).for_each(|x| filter.insert(&(x % 20)).unwrap());
+
+        let bytes = filter.to_bytes();
+        let other = CountingCuckooFilter::<16, 3, 12, 6>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(filter.len(), other.len());
+        assert!((0..20usize).all(|x| other.count(&x) >= 3));
+        assert_eq!(bytes, other.to_bytes());
+
+        assert!(matches!(
+            CountingCuckooFilter::<16, 3, 12, 5>::from_bytes(&bytes),
+            Err(CodecError::InvalidParameters)
+        ));
+
+        // The length does not match the sum of the counters.
+        let (mut header, payload) = Header::decode(&bytes).unwrap();
+        for len in [0, 59, 61] {
+            header.params[2] = len;
+            assert!(matches!(
+                CountingCuckooFilter::<16, 3, 12, 6>::from_bytes(&header.encode(payload)),
+                Err(CodecError::InvalidPayload)
+            ));
+        }
+    }
+
+    #[quickcheck]
+    fn prop_multiset(ops: Vec<(bool, u8)>) -> bool {
+        let mut filter = CountingCuckooFilter::<256, 4, 12, 3>::new();
+        let mut model = HashMap::<u8, usize>::new();
+
+        for (insert, x) in ops {
+            let n = model.entry(x).or_default();
+            if insert {
+                if filter.insert(&x).is_ok() {
+                    *n += 1;
+                }
+            } else if *n > 0 {
+                if !filter.delete(&x) {
+                    return false;
+                }
+                *n -= 1;
+            }
+        }
+
+        let len: usize = model.values().sum();
+        len == filter.len() && model.iter().all(|(x, &n)| filter.count(x) >= n)
+    }
+}
@@ -0,0 +1,381 @@
+use std::hash::{Hash, Hasher};
+
+use aabel_hash::hash::{Hasher128, HasherExt, SipHasher24};
+use log::debug;
+use rand::Rng;
+
+use super::{
+    fi_pair::FIPair,
+    fingerprint::{alt_hash, MAX_FINGERPRINT_BITS, MIN_FINGERPRINT_BITS},
+    index::Index,
+    table::PackedTable,
+    CuckooError, DEFAULT_MAX_KICKS,
+};
+
+/// The false positive rate used by [`DynCuckooFilter::with_capacity`].
+pub const DEFAULT_FPR: f64 = 0.01;
+
+/// A cuckoo filter whose dimensions are chosen at runtime, with the
+/// fingerprints bit-packed in a table on the heap.
+///
+/// The number of buckets is a power of two, so the buckets of an item are
+/// found by masking and the alternate of the alternate bucket is the original
+/// one. The fingerprints are never zero, a zero slot is an empty one.
+pub struct DynCuckooFilter<H = SipHasher24> {
+    table: PackedTable,
+    /// Number of buckets, a power of two.
+    buckets: usize,
+    /// Number of slots per bucket.
+    slots: usize,
+    /// Number of bits of a fingerprint.
+    bits: usize,
+    len: usize,
+    max_kicks: usize,
+    hasher: H,
+}
+
+impl<H> DynCuckooFilter<H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt,
+{
+    /// Creates a `DynCuckooFilter` with at least *buckets* buckets of *slots* slots,
+    /// storing fingerprints of *bits* bits. The number of buckets is rounded up
+    /// to a power of two.
+    pub fn new(buckets: usize, slots: usize, bits: usize) -> Self {
+        let hasher = <H as HasherExt>::with_rnd_seed();
+        Self::with_hasher(buckets, slots, bits, hasher)
+    }
+
+    /// Creates a `DynCuckooFilter` with at least *buckets* buckets of *slots* slots,
+    /// storing fingerprints of *bits* bits, with the hasher seeded from a given seed.
+    pub fn with_seed(buckets: usize, slots: usize, bits: usize, seed: &[u8; 16]) -> Self {
+        let hasher = <H as HasherExt>::with_seed(seed);
+        Self::with_hasher(buckets, slots, bits, hasher)
+    }
+
+    fn with_hasher(buckets: usize, slots: usize, bits: usize, hasher: H) -> Self {
+        assert!(slots > 0, "the buckets must have at least one slot");
+        assert!(
+            (MIN_FINGERPRINT_BITS..=MAX_FINGERPRINT_BITS).contains(&bits),
+            "the fingerprints must have between 4 and 32 bits"
+        );
+
+        let buckets = buckets.max(1).next_power_of_two();
+        Self {
+            table: PackedTable::new(buckets * slots, bits),
+            buckets,
+            slots,
+            bits,
+            len: 0,
+            max_kicks: DEFAULT_MAX_KICKS,
+            hasher,
+        }
+    }
+
+    /// Creates a new cuckoo filter which is expected to store a given number of
+    /// items, with a false positive rate of 1%.
+    pub fn with_capacity(num_items: usize) -> Self {
+        Self::with_capacity_fpr(num_items, DEFAULT_FPR)
+    }
+
+    /// Creates a new cuckoo filter which is expected to store a given number of
+    /// items and with an expected false positive rate.
+    pub fn with_capacity_fpr(num_items: usize, false_positive_rate: f64) -> Self {
+        let (buckets, slots, bits) = compute_optimal(num_items, false_positive_rate);
+        Self::new(buckets, slots, bits)
+    }
+
+    /// Creates a new cuckoo filter which is expected to store a given number of
+    /// items and with an expected false positive rate, with the hasher seeded
+    /// from a given seed.
+    pub fn with_capacity_fpr_seed(
+        num_items: usize,
+        false_positive_rate: f64,
+        seed: &[u8; 16],
+    ) -> Self {
+        let (buckets, slots, bits) = compute_optimal(num_items, false_positive_rate);
+        Self::with_seed(buckets, slots, bits, seed)
+    }
+
+    /// Sets the maximum number of fingerprints relocated by an insertion
+    /// before the filter is considered full.
+    pub fn with_max_kicks(mut self, max_kicks: usize) -> Self {
+        self.max_kicks = max_kicks;
+        self
+    }
+
+    /// Returns the maximum number of fingerprints relocated by an insertion.
+    pub fn max_kicks(&self) -> usize {
+        self.max_kicks
+    }
+
+    /// Returns the seed of the hasher.
+    pub fn seed(&self) -> [u8; 16] {
+        self.hasher.seed()
+    }
+
+    /// Returns the number of buckets.
+    pub fn number_of_buckets(&self) -> usize {
+        self.buckets
+    }
+
+    /// Returns the number of slots per bucket.
+    pub fn bucket_size(&self) -> usize {
+        self.slots
+    }
+
+    /// Returns the number of bits of a fingerprint.
+    pub fn fingerprint_bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Returns the number of slots of the filter.
+    pub fn capacity(&self) -> usize {
+        self.table.slots()
+    }
+
+    /// Returns the number of bytes used by the fingerprints.
+    pub fn size_in_bytes(&self) -> usize {
+        self.table.size_in_bytes()
+    }
+
+    /// Returns the ratio of used slots.
+    pub fn load_factor(&self) -> f64 {
+        self.len as f64 / self.capacity() as f64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Inserts an item in the filter.
+    ///
+    /// When both buckets of the item are full, fingerprints picked at random
+    /// are relocated to their alternate bucket. If no free slot is found within
+    /// the maximum number of kicks, the relocations are undone and an error is
+    /// returned, so no previously inserted item is lost.
+    pub fn insert<T>(&mut self, data: &T) -> Result<(), CuckooError>
+    where
+        T: Hash,
+    {
+        let (fp, idx) = self.fingerprint_index(data);
+        debug!("INSERT | {fp:X} | {idx}");
+
+        let alt = self.alt(idx, fp);
+        if self.put(idx, fp) || self.put(alt, fp) {
+            return Ok(());
+        }
+
+        self.rearrange(alt, fp)
+    }
+
+    /// Determines if an item belongs to the filter.
+    pub fn contains<T>(&self, data: &T) -> bool
+    where
+        T: Hash,
+    {
+        let (fp, idx) = self.fingerprint_index(data);
+        self.find(idx, fp).is_some() || self.find(self.alt(idx, fp), fp).is_some()
+    }
+
+    /// Deletes one occurrence of an item from either of its buckets, returning
+    /// false if the item was not found.
+    ///
+    /// Only inserted items should be deleted, deleting a false positive removes
+    /// the fingerprint of another item.
+    pub fn delete<T>(&mut self, data: &T) -> bool
+    where
+        T: Hash,
+    {
+        let (fp, idx) = self.fingerprint_index(data);
+        debug!("DELETE | {fp:X} | {idx}");
+
+        let slot = self
+            .find(idx, fp)
+            .or_else(|| self.find(self.alt(idx, fp), fp));
+
+        match slot {
+            Some(slot) => {
+                self.table.set(slot, 0);
+                self.len -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the non zero fingerprint of an item and its first bucket.
+    fn fingerprint_index<T>(&self, data: &T) -> (u32, usize)
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, self.bits, self.buckets);
+        (*fi.fp.as_ref(), *fi.idx.as_ref())
+    }
+
+    /// Returns the alternate bucket of a fingerprint stored in a bucket.
+    #[inline]
+    fn alt(&self, idx: usize, fp: u32) -> usize {
+        *Index::from(idx).alt(alt_hash(fp), self.buckets).as_ref()
+    }
+
+    /// Returns the slot of a bucket which stores a fingerprint.
+    fn find(&self, idx: usize, fp: u32) -> Option<usize> {
+        let first = idx * self.slots;
+        (first..first + self.slots).find(|&s| self.table.get(s) == fp)
+    }
+
+    fn put(&mut self, idx: usize, fp: u32) -> bool {
+        match self.find(idx, 0) {
+            Some(slot) => {
+                self.table.set(slot, fp);
+                self.len += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn rearrange(&mut self, idx: usize, fp: u32) -> Result<(), CuckooError> {
+        debug!("REARRANGE | {fp:X} | {idx}");
+
+        let mut rng = rand::thread_rng();
+        let (mut idx, mut fp) = (idx, fp);
+
+        // The relocated fingerprints, to undo the kicks on failure.
+        let mut kicked = Vec::new();
+
+        for _ in 0..self.max_kicks {
+            let slot = idx * self.slots + rng.gen_range(0..self.slots);
+            let other = self.table.get(slot);
+            self.table.set(slot, fp);
+            kicked.push((slot, other));
+
+            fp = other;
+            idx = self.alt(idx, fp);
+            if self.put(idx, fp) {
+                debug!("SUCCESS | {fp:X} | {idx} | KICKS {}", kicked.len());
+                return Ok(());
+            }
+        }
+
+        // Put back the relocated fingerprints, dropping the new one.
+        for (slot, other) in kicked.into_iter().rev() {
+            self.table.set(slot, other);
+        }
+
+        debug!("FULL | {} KICKS", self.max_kicks);
+        Err(CuckooError::Full {
+            kicks: self.max_kicks,
+        })
+    }
+}
+
+/// Returns the number of buckets, slots per bucket and fingerprint bits for
+/// a given number of items and false positive rate.
+///
+/// Following Fan et al., small buckets are used for high rates and larger
+/// ones for low rates, each with the load factor the random walk eviction
+/// reaches reliably. A lookup compares the fingerprint against the `2 * slots`
+/// slots of two buckets, so the rate is at most `2 * slots / 2^bits`.
+fn compute_optimal(items: usize, false_positive_rate: f64) -> (usize, usize, usize) {
+    let (slots, load) = if false_positive_rate > 0.002 {
+        (2, 0.8)
+    } else if false_positive_rate > 0.000_01 {
+        (4, 0.9)
+    } else {
+        (8, 0.95)
+    };
+
+    let bits = (2.0 * slots as f64 / false_positive_rate).log2().ceil() as usize;
+    let buckets = (items as f64 / (slots as f64 * load)).ceil() as usize;
+
+    (
+        buckets,
+        slots,
+        bits.clamp(MIN_FINGERPRINT_BITS, MAX_FINGERPRINT_BITS),
+    )
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+    use std::collections::HashSet;
+
+    #[test]
+    fn simple_() {
+        let mut filter: DynCuckooFilter = DynCuckooFilter::new(100, 4, 12);
+        assert_eq!(128, filter.number_of_buckets());
+        assert_eq!(512, filter.capacity());
+
+        filter.insert(&"AAAA").unwrap();
+        assert!(filter.contains(&"AAAA"));
+        assert_eq!(1, filter.len());
+
+        assert!(filter.delete(&"AAAA"));
+        assert!(!filter.contains(&"AAAA"));
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn optimal_() {
+        assert_eq!((12_500, 2, 9), compute_optimal(20_000, 0.01));
+        assert_eq!((27_778, 4, 13), compute_optimal(100_000, 0.001));
+        assert_eq!((131_579, 8, 21), compute_optimal(1_000_000, 0.000_01));
+        assert_eq!(4, compute_optimal(10, 0.5).2);
+    }
+
+    #[test]
+    fn capacity_() {
+        let mut filter: DynCuckooFilter = DynCuckooFilter::with_capacity(200_000);
+        assert!(filter.number_of_buckets().is_power_of_two());
+        assert!(filter.capacity() >= 200_000);
+
+        // The filter is on the heap and holds the items it was sized for.
+        assert!((0..200_000usize).all(|x| filter.insert(&x).is_ok()));
+        assert!((0..200_000usize).all(|x| filter.contains(&x)));
+    }
+
+    #[test]
+    fn fpr_() {
+        let mut filter: DynCuckooFilter = DynCuckooFilter::with_capacity_fpr(10_000, 0.01);
+        (0..10_000usize).for_each(|x| filter.insert(&x).unwrap());
+
+        let fps = (10_000..110_000usize)
+            .filter(|x| filter.contains(x))
+            .count();
+        let measured = fps as f64 / 100_000.0;
+        assert!(measured < 0.01, "measured={measured}");
+    }
+
+    #[test]
+    fn full_() {
+        let mut filter: DynCuckooFilter = DynCuckooFilter::new(64, 4, 8);
+
+        // Fill the filter, a failed insertion must not lose any fingerprint.
+        let mut inserted = Vec::new();
+        for x in 0..1_000usize {
+            match filter.insert(&x) {
+                Ok(()) => inserted.push(x),
+                Err(CuckooError::Full { .. }) => {
+                    assert_eq!(inserted.len(), filter.len());
+                    assert!(inserted.iter().all(|x| filter.contains(x)));
+                }
+            }
+        }
+
+        assert!(filter.load_factor() > 0.9, "{}", filter.load_factor());
+    }
+
+    #[quickcheck]
+    fn prop_dyn_cuckoo_filter(xs: HashSet<usize>) -> bool {
+        let mut filter: DynCuckooFilter = DynCuckooFilter::with_capacity(1000);
+        xs.iter().for_each(|x| filter.insert(x).unwrap());
+        xs.iter().all(|x| filter.contains(x))
+    }
+}
@@ -0,0 +1,21 @@
+use std::{error::Error, fmt::Display};
+
+/// The errors returned by the cuckoo filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CuckooError {
+    /// No free slot was found within the maximum number of kicks. The filter
+    /// is left unchanged.
+    Full { kicks: usize },
+}
+
+impl Display for CuckooError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Full { kicks } => {
+                write!(f, "the filter is full, no free slot after {kicks} kicks")
+            }
+        }
+    }
+}
+
+impl Error for CuckooError {}
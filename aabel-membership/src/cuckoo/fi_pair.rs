@@ -1,59 +1,50 @@
-use aabel_hash::hash::HashExt;
-use std::{fmt::Debug, hash::Hasher, marker::PhantomData};
+use aabel_hash::hash::{Hash128Ext, Hasher128};
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
 
-use super::{fingerprint::Fingerprint, index::Index};
+use super::{
+    fingerprint::{alt_hash, Fingerprint},
+    index::Index,
+};
 
-#[derive(Default, Hash)]
-pub struct FIPair<H = std::collections::hash_map::DefaultHasher>
-where
-    H: Default + Hasher,
-{
+#[derive(Clone, Copy, Hash)]
+pub struct FIPair {
     pub fp: Fingerprint,
     pub idx: Index,
-    _p: PhantomData<H>,
 }
 
-impl<H> Debug for FIPair<H>
-where
-    H: Default + Hasher,
-{
+impl Debug for FIPair {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "({:?}, {:?})", self.fp, self.idx)
     }
 }
 
-impl<H> FIPair<H>
-where
-    H: Default + Hasher,
-{
+impl FIPair {
     pub(crate) fn new(fp: Fingerprint, idx: Index) -> Self {
-        Self {
-            fp,
-            idx,
-            _p: PhantomData,
-        }
+        Self { fp, idx }
     }
 
-    pub fn from_data<T>(data: &T) -> Self
+    /// Returns the fingerprint of *bits* bits and the bucket among *buckets*
+    /// buckets of an item, taken from the two independent halves of its 128
+    /// bits hash.
+    pub fn from_data<T, H>(data: &T, hasher: H, bits: usize, buckets: usize) -> Self
     where
-        T: HashExt,
+        T: Hash,
+        H: Hasher + Hasher128,
     {
-        let (fp, idx) = data.get_hash_deconstructed::<H>();
-        let fp = Fingerprint::from(fp);
-        let idx = Index::from(idx);
-        Self {
-            fp,
-            idx,
-            _p: PhantomData,
-        }
+        let (h1, h2) = data.get_hash128_deconstructed(hasher);
+        let fp = Fingerprint::new((h2 >> 32) as u32, bits);
+        let idx = Index::bucket(h1, buckets);
+        Self { fp, idx }
     }
 
-    pub fn alt(self) -> Self {
-        let idx = self.fp.get_hash::<H>() ^ (*self.idx.as_ref() as u64);
+    /// Returns the pair for the alternate bucket among *buckets* buckets.
+    pub fn alt(self, buckets: usize) -> Self {
         Self {
             fp: self.fp,
-            idx: Index::from(idx as usize),
-            _p: PhantomData,
+            idx: self.idx.alt(alt_hash(*self.fp.as_ref()), buckets),
         }
     }
 }
@@ -61,44 +52,57 @@ where
 #[cfg(test)]
 mod utests {
     use super::*;
+    use aabel_hash::hash::{HasherExt, SipHasher24};
+
+    fn hasher() -> SipHasher24 {
+        SipHasher24::with_seed(&[7; 16])
+    }
 
     #[test]
     fn debug_() {
         let data = "testing, testing";
-        let fi = FIPair::<std::collections::hash_map::DefaultHasher>::from_data::<_>(&data);
+        let fi = FIPair::from_data(&data, hasher(), 8, 1 << 20);
         println!("FIPAIR_DBG: {:?}", fi);
     }
 
     #[test]
     fn fipair_new() {
         let data = "testing, testing";
-        let fi = FIPair::<std::collections::hash_map::DefaultHasher>::from_data::<_>(&data);
+        let fi = FIPair::from_data(&data, hasher(), 8, 1 << 20);
         assert_ne!(0, *fi.fp.as_ref());
         assert_ne!(0, *fi.idx.as_ref());
     }
 
+    #[test]
+    fn stable_() {
+        // The pairs only depend on the seed, persisted filters stay valid.
+        let fi = FIPair::from_data(&12345u64, hasher(), 16, 1 << 20);
+        assert_eq!(0x544B, *fi.fp.as_ref());
+        assert_eq!(0xC_B5AE, *fi.idx.as_ref());
+    }
+
     #[test]
     fn fipair_alt() {
-        let fp = Fingerprint::from(10);
+        let fp = Fingerprint::new(10, 8);
         let idx = Index::from(123u32);
-        let fi1 = FIPair::<std::collections::hash_map::DefaultHasher>::new(fp, idx);
+        let fi1 = FIPair::new(fp, idx);
 
-        let fi2 = fi1.alt();
+        let fi2 = fi1.alt(1000);
         assert_eq!(&fi2.fp, &fp);
 
-        let fi3 = fi2.alt();
+        let fi3 = fi2.alt(1000);
         assert_eq!(&fi3.fp, &fp);
         assert_eq!(&fi3.idx, &idx);
     }
 
     #[test]
     fn alt_alt() {
-        let fp = Fingerprint::from(10);
+        let fp = Fingerprint::new(10, 8);
         let idx = Index::from(123u32);
-        let fi1 = FIPair::<std::collections::hash_map::DefaultHasher>::new(fp, idx);
+        let fi1 = FIPair::new(fp, idx);
 
-        let fi2 = fi1.alt();
-        let fi3 = fi2.alt();
+        let fi2 = fi1.alt(1000);
+        let fi3 = fi2.alt(1000);
 
         assert_eq!(&fi3.fp, &fp);
         assert_eq!(&fi3.idx, &idx);
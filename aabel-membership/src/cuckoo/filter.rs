@@ -1,28 +1,47 @@
-use std::{fmt::Debug, hash::Hasher, marker::PhantomData};
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
 
-use aabel_hash::hash::HashExt;
+use aabel_codec::{CodecError, Header, SketchKind};
+use aabel_hash::hash::{Hasher128, HasherExt, HasherId, SipHasher24};
 use log::debug;
-
-use crate::cuckoo::fi_pair::FIPair;
-
-use super::bucket::Bucket;
-
-pub struct CuckooFilter<
-    const B: usize,
-    const N: usize,
-    H = std::collections::hash_map::DefaultHasher,
-> where
-    H: Default + Hasher,
-{
-    buckets: [Bucket<N>; B],
+use rand::Rng;
+
+use crate::{
+    cuckoo::fi_pair::FIPair,
+    prefetch::{prefetch, BATCH},
+};
+
+use super::{bucket::Bucket, CuckooError};
+
+/// The default maximum number of fingerprints relocated by an insertion.
+pub const DEFAULT_MAX_KICKS: usize = 500;
+
+/// A cuckoo filter with *B* buckets of *N* slots, storing fingerprints of
+/// *F* bits, between 4 and 32, with `N * F` at most 128.
+///
+/// A lookup compares the fingerprint of an item against the `2 * N` slots of
+/// its two buckets, and a fingerprint matches one of another item with
+/// probability `1 / (2^F - 1)`, so the false positive rate is at most
+/// `2 * N / (2^F - 1)` and about `2 * N * load / 2^F` for a given load factor.
+/// Each additional bit of fingerprint halves the rate: with 4 slots per
+/// bucket, 8 bits give about 3%, 12 bits 0.2% and 16 bits 0.01%.
+///
+/// The fingerprint and the bucket of an item are taken from the two halves of
+/// its seeded 128 bits hash. Filters created with the same seed and dimensions
+/// are compatible, and stay so across toolchain upgrades.
+///
+/// Any number of buckets works, the alternate bucket of the alternate bucket
+/// being the original one, but a power of two replaces the divisions by masks.
+pub struct CuckooFilter<const B: usize, const N: usize, const F: usize = 8, H = SipHasher24> {
+    buckets: [Bucket<N, F>; B],
     len: usize,
-    _p: PhantomData<H>,
+    max_kicks: usize,
+    hasher: H,
 }
 
-impl<const B: usize, const N: usize, H> Debug for CuckooFilter<B, N, H>
-where
-    H: Default + Hasher,
-{
+impl<const B: usize, const N: usize, const F: usize, H> Debug for CuckooFilter<B, N, F, H> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let x = self.buckets.iter().fold("".to_owned(), |acc, b| {
             if acc.is_empty() {
@@ -35,65 +54,214 @@ where
     }
 }
 
-impl<const B: usize, const N: usize, H> Default for CuckooFilter<B, N, H>
+impl<const B: usize, const N: usize, const F: usize, H> Default for CuckooFilter<B, N, F, H>
 where
-    H: Default + Hasher,
+    H: Copy + Hasher + Hasher128 + HasherExt,
 {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<const B: usize, const N: usize, H> CuckooFilter<B, N, H>
+impl<const B: usize, const N: usize, const F: usize, H> CuckooFilter<B, N, F, H>
 where
-    H: Default + Hasher,
+    H: Copy + Hasher + Hasher128 + HasherExt,
 {
+    /// Creates a `CuckooFilter` with the hasher seeded from a random seed.
     pub fn new() -> Self {
+        let hasher = <H as HasherExt>::with_rnd_seed();
+        Self::with_hasher(hasher)
+    }
+
+    /// Creates a `CuckooFilter` with the hasher seeded from a given seed.
+    pub fn with_seed(seed: &[u8; 16]) -> Self {
+        let hasher = <H as HasherExt>::with_seed(seed);
+        Self::with_hasher(hasher)
+    }
+
+    fn with_hasher(hasher: H) -> Self {
         Self {
-            buckets: [Bucket::<N>::new(); B],
+            buckets: [Bucket::<N, F>::new(); B],
             len: 0,
-            _p: PhantomData,
+            max_kicks: DEFAULT_MAX_KICKS,
+            hasher,
+        }
+    }
+
+    /// Returns the seed of the hasher.
+    pub fn seed(&self) -> [u8; 16] {
+        self.hasher.seed()
+    }
+
+    /// Sets the maximum number of fingerprints relocated by an insertion
+    /// before the filter is considered full.
+    pub fn with_max_kicks(mut self, max_kicks: usize) -> Self {
+        self.max_kicks = max_kicks;
+        self
+    }
+
+    /// Returns the maximum number of fingerprints relocated by an insertion.
+    pub fn max_kicks(&self) -> usize {
+        self.max_kicks
+    }
+
+    /// Inserts an item in the filter.
+    ///
+    /// When both buckets of the item are full, fingerprints picked at random
+    /// are relocated to their alternate bucket. If no free slot is found within
+    /// the maximum number of kicks, the relocations are undone and an error is
+    /// returned, so no previously inserted item is lost.
+    pub fn insert<T>(&mut self, data: &T) -> Result<(), CuckooError>
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        self.insert_pair(fi)
+    }
+
+    pub fn contains<T>(&self, data: &T) -> bool
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        self.contains_pair(fi)
+    }
+
+    /// Returns the number of copies of an item's fingerprint in its two buckets.
+    ///
+    /// Every inserted copy of the item is counted, but copies of other items
+    /// with the same fingerprint may be counted too, so the count is an upper
+    /// bound of the item's multiplicity.
+    pub fn count<T>(&self, data: &T) -> usize
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        let alt = fi.alt(B);
+
+        let (idx, alt_idx) = (*fi.idx.as_ref(), *alt.idx.as_ref());
+        let n = self.buckets[idx].count(fi.fp);
+        if alt_idx == idx {
+            n
+        } else {
+            n + self.buckets[alt_idx].count(fi.fp)
+        }
+    }
+
+    /// Deletes one occurrence of an item from either of its buckets, returning
+    /// false if the item was not found.
+    ///
+    /// Only inserted items should be deleted, deleting a false positive removes
+    /// the fingerprint of another item.
+    pub fn delete<T>(&mut self, data: &T) -> bool
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        debug!("DELETE | {:?}", &fi);
+
+        if self.remove(&fi) {
+            return true;
+        }
+
+        let fi = fi.alt(B);
+        debug!("DELETE | {:?} | ALT", &fi);
+        self.remove(&fi)
+    }
+
+    /// Inserts a batch of items, returning for each item if it was inserted.
+    ///
+    /// The items are hashed ahead of probing their buckets, so the memory
+    /// loads of several items overlap.
+    pub fn insert_many<T>(&mut self, data: &[T]) -> Vec<bool>
+    where
+        T: Hash,
+    {
+        let mut res = Vec::with_capacity(data.len());
+        let mut fis = Vec::with_capacity(BATCH);
+
+        for chunk in data.chunks(BATCH) {
+            self.batch_pairs(chunk, &mut fis);
+            res.extend(fis.drain(..).map(|fi| self.insert_pair(fi).is_ok()));
+        }
+
+        res
+    }
+
+    /// Determines for each item of a batch if it belongs to the filter.
+    pub fn contains_many<T>(&self, data: &[T]) -> Vec<bool>
+    where
+        T: Hash,
+    {
+        let mut res = Vec::with_capacity(data.len());
+        let mut fis = Vec::with_capacity(BATCH);
+
+        for chunk in data.chunks(BATCH) {
+            self.batch_pairs(chunk, &mut fis);
+            res.extend(fis.drain(..).map(|fi| self.contains_pair(fi)));
         }
+
+        res
+    }
+
+    /// Returns the number of bits of the packed buckets.
+    pub fn number_of_bits(&self) -> usize {
+        B * Bucket::<N, F>::BITS
     }
 
-    pub fn insert<T>(&mut self, data: &T) -> bool
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Hashes a batch of items and prefetches both their buckets.
+    fn batch_pairs<T>(&self, data: &[T], fis: &mut Vec<FIPair>)
     where
-        T: HashExt,
+        T: Hash,
     {
+        for item in data {
+            let fi = FIPair::from_data(item, self.hasher, F, B);
+            let alt = fi.alt(B);
+
+            prefetch(&self.buckets, *fi.idx.as_ref());
+            prefetch(&self.buckets, *alt.idx.as_ref());
+            fis.push(fi);
+        }
+    }
+
+    fn insert_pair(&mut self, fi: FIPair) -> Result<(), CuckooError> {
         // Try to add it to the first index.
-        let fi = FIPair::<H>::from_data(data);
         debug!("INSERT | {:?}", &fi);
 
         if self.put(&fi) {
-            debug!("SUCCESS | {:?} | {} | INIT", fi, fi.idx % B);
-            return true;
+            debug!("SUCCESS | {:?} | {} | INIT", fi, *fi.idx.as_ref());
+            return Ok(());
         }
 
         // Try to add it to the second index.
-        let fi = fi.alt();
+        let fi = fi.alt(B);
         if self.put(&fi) {
-            debug!("SUCCESS | {:?} | {} | ALT", fi, fi.idx % B);
-            return true;
+            debug!("SUCCESS | {:?} | {} | ALT", fi, *fi.idx.as_ref());
+            return Ok(());
         }
 
         // Rearrange the fingerprints
         self.rearrange(fi)
     }
 
-    pub fn contains<T>(&self, data: &T) -> bool
-    where
-        T: HashExt,
-    {
-        let fi = FIPair::<H>::from_data(data);
+    fn contains_pair(&self, fi: FIPair) -> bool {
         let fp = fi.fp;
         debug!("CONTAINS | {:?} | INIT", &fi);
 
-        let r = self.buckets[fi.idx % B]
+        let r = self.buckets[*fi.idx.as_ref()]
             .contains(fi.fp)
             .or_else(|| {
-                let fi = fi.alt();
+                let fi = fi.alt(B);
                 debug!("CONTAINS | {:?} | ALT", &fi);
-                self.buckets[fi.idx % B].contains(fi.fp)
+                self.buckets[*fi.idx.as_ref()].contains(fi.fp)
             })
             .is_some();
 
@@ -101,39 +269,56 @@ where
         r
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.len == 0
-    }
-
-    pub fn len(&self) -> usize {
-        self.len
-    }
-
-    fn swap(&mut self, fi: &FIPair<H>) -> FIPair<H> {
-        let other = self.buckets[fi.idx % B].swap(0, fi.fp);
-        FIPair::new(other, fi.idx).alt()
-    }
-
-    fn rearrange(&mut self, fi: FIPair<H>) -> bool {
+    fn rearrange(&mut self, fi: FIPair) -> Result<(), CuckooError> {
         debug!("REARRANGE | {:?}", &fi);
 
+        let mut rng = rand::thread_rng();
         let mut current = fi;
 
-        for _ in 0..3 {
-            {
-                current = self.swap(&current);
-            }
+        // The relocated fingerprints, to undo the kicks on failure.
+        let mut kicked = Vec::new();
+
+        for _ in 0..self.max_kicks {
+            let idx = *current.idx.as_ref();
+            let slot = rng.gen_range(0..N);
+            let other = self.buckets[idx].swap(slot, current.fp);
+            kicked.push((idx, slot, other));
 
+            current = FIPair::new(other, current.idx).alt(B);
             if self.put(&current) {
-                return true;
+                debug!(
+                    "SUCCESS | {:?} | {} | KICKS {}",
+                    current,
+                    *current.idx.as_ref(),
+                    kicked.len()
+                );
+                return Ok(());
             }
         }
 
-        false
+        // Put back the relocated fingerprints, dropping the new one.
+        for (idx, slot, other) in kicked.into_iter().rev() {
+            self.buckets[idx].swap(slot, other);
+        }
+
+        debug!("FULL | {} KICKS", self.max_kicks);
+        Err(CuckooError::Full {
+            kicks: self.max_kicks,
+        })
+    }
+
+    fn remove(&mut self, fi: &FIPair) -> bool {
+        let idx = *fi.idx.as_ref();
+        if self.buckets[idx].remove(fi.fp) {
+            self.len -= 1;
+            true
+        } else {
+            false
+        }
     }
 
-    fn put(&mut self, fi: &FIPair<H>) -> bool {
-        let idx = fi.idx % B;
+    fn put(&mut self, fi: &FIPair) -> bool {
+        let idx = *fi.idx.as_ref();
         if self.buckets[idx].insert(fi.fp) {
             self.len += 1;
             true
@@ -143,42 +328,188 @@ where
     }
 }
 
+impl<const B: usize, const N: usize, const F: usize, H> CuckooFilter<B, N, F, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt + HasherId,
+{
+    /// The number of bytes of an encoded bucket.
+    const BUCKET_BYTES: usize = Bucket::<N, F>::BITS.div_ceil(8);
+
+    /// Encodes the buckets, their dimensions and the hasher identifier.
+    ///
+    /// Each bucket is encoded on the bytes of its packed slots, in little endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let params = vec![B as u64, N as u64, self.len as u64, F as u64];
+        let header = Header::new(
+            SketchKind::CuckooFilter,
+            H::HASHER_ID,
+            self.hasher.seed(),
+            params,
+        );
+
+        let payload: Vec<u8> = self
+            .buckets
+            .iter()
+            .flat_map(|b| b.bits().to_le_bytes()[..Self::BUCKET_BYTES].to_vec())
+            .collect();
+        header.encode(&payload)
+    }
+
+    /// Decodes the buckets encoded with [`CuckooFilter::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (header, payload) = Header::decode(bytes)?;
+        header.expect_kind(SketchKind::CuckooFilter)?;
+        header.expect_hasher(H::HASHER_ID)?;
+
+        let [b, n, len, f] = header.expect_params()?;
+        if b != B as u64 || n != N as u64 || f != F as u64 {
+            return Err(CodecError::InvalidParameters);
+        }
+
+        if payload.len() != B * Self::BUCKET_BYTES {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        let mut filter = Self::with_seed(&header.seed);
+        let chunks = payload.chunks_exact(Self::BUCKET_BYTES);
+        for (bucket, bytes) in filter.buckets.iter_mut().zip(chunks) {
+            let mut bits = [0; 16];
+            bits[..bytes.len()].copy_from_slice(bytes);
+            *bucket =
+                Bucket::from_bits(u128::from_le_bytes(bits)).ok_or(CodecError::InvalidPayload)?;
+        }
+
+        // The length is the number of stored fingerprints.
+        if filter.buckets.iter().map(|b| b.used()).sum::<usize>() as u64 != len {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        filter.len = len as usize;
+        Ok(filter)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use aabel_hash::hash::HasherExt;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Bucket, CuckooFilter};
+    use crate::cuckoo::fingerprint::Fingerprint;
+
+    #[derive(Serialize, Deserialize)]
+    struct CuckooFilterRepr {
+        len: usize,
+        fingerprint_bits: usize,
+        seed: [u8; 16],
+        buckets: Vec<Vec<Option<u32>>>,
+    }
+
+    impl<const B: usize, const N: usize, const F: usize, H> Serialize for CuckooFilter<B, N, F, H>
+    where
+        H: HasherExt,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let buckets = self
+                .buckets
+                .iter()
+                .map(|b| (0..N).map(|i| b.get(i).map(|fp| *fp.as_ref())).collect())
+                .collect();
+
+            CuckooFilterRepr {
+                len: self.len,
+                fingerprint_bits: F,
+                seed: self.hasher.seed(),
+                buckets,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, const B: usize, const N: usize, const F: usize, H> Deserialize<'de>
+        for CuckooFilter<B, N, F, H>
+    where
+        H: HasherExt,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let repr = CuckooFilterRepr::deserialize(deserializer)?;
+            if repr.fingerprint_bits != F
+                || repr.buckets.len() != B
+                || repr.buckets.iter().any(|b| b.len() != N)
+            {
+                return Err(D::Error::custom("the buckets do not match the filter size"));
+            }
+
+            let mut buckets = [Bucket::<N, F>::new(); B];
+            for (bucket, slots) in buckets.iter_mut().zip(repr.buckets) {
+                for (i, fp) in slots.into_iter().enumerate() {
+                    let fp = match fp {
+                        Some(fp) if fp == 0 || fp as u64 >= 1 << F => {
+                            return Err(D::Error::custom("a fingerprint does not fit its slot"))
+                        }
+                        fp => fp.and_then(Fingerprint::from_slot),
+                    };
+                    bucket.set(i, fp);
+                }
+            }
+
+            if buckets.iter().map(|b| b.used()).sum::<usize>() != repr.len {
+                return Err(D::Error::custom(
+                    "the length does not match the fingerprints",
+                ));
+            }
+
+            Ok(Self {
+                buckets,
+                len: repr.len,
+                max_kicks: super::DEFAULT_MAX_KICKS,
+                hasher: H::with_seed(&repr.seed),
+            })
+        }
+    }
+}
+
 #[cfg(test)]
 mod utests {
     use super::*;
+    use quickcheck_macros::quickcheck;
+    use std::collections::{HashMap, HashSet};
+
+    const SEED: [u8; 16] = [42; 16];
 
     #[test]
     fn insert_() {
-        let mut filter = CuckooFilter::<12, 1>::new();
+        let mut filter = CuckooFilter::<12, 1>::with_seed(&SEED);
 
         // Add it in the first bucket
         let r = filter.insert(&"testing");
-        assert!(r);
+        assert!(r.is_ok());
         println!("CUCKOO_DBG (1): {:?}", filter);
 
         // Add it to the second bucket
         let r = filter.insert(&"testing");
-        assert!(r);
+        assert!(r.is_ok());
         println!("CUCKOO_DBG (2): {:?}", filter);
 
         // No place to add it.
         let r = filter.insert(&"testing");
-        assert!(!r);
+        assert_eq!(Err(CuckooError::Full { kicks: 500 }), r);
+        assert_eq!(2, filter.len());
         println!("CUCKOO_DBG (3): {:?}", filter);
     }
 
     #[test]
     fn contains_() {
-        let mut filter = CuckooFilter::<12, 1>::new();
+        let mut filter = CuckooFilter::<12, 1>::with_seed(&SEED);
 
         // Add it in the first bucket
         let r = filter.insert(&"AAAA");
-        assert!(r);
+        assert!(r.is_ok());
         println!("CUCKOO_DBG (1): {:?}", filter);
 
         // Add it to the second bucket
         let r = filter.insert(&"BBBB");
-        assert!(r);
+        assert!(r.is_ok());
         println!("CUCKOO_DBG (2): {:?}", filter);
 
         // The filter should return true for the two elements
@@ -188,4 +519,222 @@ mod utests {
         let r = filter.contains(&"BBBB");
         assert!(r);
     }
+
+    #[test]
+    fn delete_() {
+        let mut filter = CuckooFilter::<12, 1>::with_seed(&SEED);
+        assert!(filter.insert(&"AAAA").is_ok());
+        assert!(filter.insert(&"AAAA").is_ok());
+        assert_eq!(2, filter.len());
+
+        // Each delete removes one occurrence, from either bucket.
+        assert!(filter.delete(&"AAAA"));
+        assert!(filter.contains(&"AAAA"));
+        assert_eq!(1, filter.len());
+
+        assert!(filter.delete(&"AAAA"));
+        assert!(!filter.contains(&"AAAA"));
+        assert!(filter.is_empty());
+
+        assert!(!filter.delete(&"AAAA"));
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn count_() {
+        let mut filter = CuckooFilter::<12, 2>::with_seed(&SEED);
+        assert_eq!(0, filter.count(&"AAAA"));
+
+        // The copies are spread over the two buckets of the item.
+        (0..3).for_each(|_| filter.insert(&"AAAA").unwrap());
+        assert_eq!(3, filter.count(&"AAAA"));
+
+        assert!(filter.delete(&"AAAA"));
+        assert_eq!(2, filter.count(&"AAAA"));
+    }
+
+    #[quickcheck]
+    fn prop_insert_delete(ops: Vec<(bool, u8)>) -> bool {
+        let mut filter = CuckooFilter::<1024, 4>::new();
+        let mut model = HashMap::<u8, usize>::new();
+
+        for (insert, x) in ops {
+            let n = model.entry(x).or_default();
+            if insert {
+                // Keep the copies of an item under the slots of its two buckets.
+                if *n < 4 && filter.insert(&x).is_ok() {
+                    *n += 1;
+                }
+            } else if *n > 0 {
+                if !filter.delete(&x) {
+                    return false;
+                }
+                *n -= 1;
+            }
+        }
+
+        let len: usize = model.values().sum();
+        len == filter.len()
+            && model
+                .iter()
+                .all(|(x, &n)| filter.count(x) >= n && (n == 0 || filter.contains(x)))
+    }
+
+    #[quickcheck]
+    fn prop_any_buckets(xs: HashSet<u32>) -> bool {
+        // Items relocated by the kicks are found with any number of buckets.
+        let mut filter = CuckooFilter::<37, 4>::new();
+        let inserted: Vec<_> = xs.iter().filter(|x| filter.insert(x).is_ok()).collect();
+        inserted.len() == filter.len() && inserted.iter().all(|x| filter.contains(x))
+    }
+
+    #[test]
+    fn kicks_() {
+        let mut filter = CuckooFilter::<12, 1>::with_seed(&SEED).with_max_kicks(0);
+        assert_eq!(0, filter.max_kicks());
+
+        assert!(filter.insert(&"AAAA").is_ok());
+        assert!(filter.insert(&"AAAA").is_ok());
+        assert_eq!(Err(CuckooError::Full { kicks: 0 }), filter.insert(&"AAAA"));
+    }
+
+    #[test]
+    fn full_() {
+        let mut filter = CuckooFilter::<64, 4>::new();
+
+        // Fill the filter, a failed insertion must not lose any fingerprint.
+        let mut inserted = Vec::new();
+        for x in 0..1_000usize {
+            match filter.insert(&x) {
+                Ok(()) => inserted.push(x),
+                Err(CuckooError::Full { .. }) => {
+                    assert_eq!(inserted.len(), filter.len());
+                    assert!(inserted.iter().all(|x| filter.contains(x)));
+                }
+            }
+        }
+
+        // The random kicks fill most of the slots.
+        assert!(inserted.len() > 64 * 4 * 9 / 10, "{}", inserted.len());
+    }
+
+    #[test]
+    fn seed_() {
+        let mut filter1 = CuckooFilter::<1024, 4>::with_seed(&SEED);
+        let mut filter2 = CuckooFilter::<1024, 4>::with_seed(&SEED);
+        (0..500usize).for_each(|x| filter1.insert(&x).unwrap());
+        (0..500usize).for_each(|x| filter2.insert(&x).unwrap());
+
+        // The fingerprints and buckets only depend on the seed.
+        assert_eq!(filter1.to_bytes(), filter2.to_bytes());
+
+        let filter3 = CuckooFilter::<1024, 4>::with_seed(&[7; 16]);
+        assert_ne!(filter1.seed(), filter3.seed());
+    }
+
+    fn measured_fpr<const F: usize>() -> f64 {
+        let mut filter = CuckooFilter::<1024, 4, F>::new();
+        (0..3_000usize).for_each(|x| filter.insert(&x).unwrap());
+
+        let fps = (3_000..103_000usize).filter(|x| filter.contains(x)).count();
+        fps as f64 / 100_000.0
+    }
+
+    #[test]
+    fn widths_() {
+        let rates = [
+            measured_fpr::<4>(),
+            measured_fpr::<8>(),
+            measured_fpr::<12>(),
+            measured_fpr::<16>(),
+        ];
+
+        // The rate stays under its bound and drops with each additional bit.
+        for (f, rate) in [4, 8, 12].into_iter().zip(rates) {
+            let bound = 8.0 / ((1u64 << f) - 1) as f64;
+            assert!(rate < bound, "f={f} rate={rate} bound={bound}");
+        }
+        assert!(rates.windows(2).all(|w| w[1] < w[0]), "{rates:?}");
+    }
+
+    #[test]
+    fn many_() {
+        let mut filter = CuckooFilter::<1024, 4>::new();
+        let xs: Vec<usize> = (0..500).collect();
+        let inserted = filter.insert_many(&xs);
+
+        let ys: Vec<usize> = (250..750).collect();
+        let expected: Vec<bool> = ys.iter().map(|y| filter.contains(y)).collect();
+        assert_eq!(expected, filter.contains_many(&ys));
+
+        let found = filter.contains_many(&xs);
+        assert!(inserted.iter().zip(found).all(|(&i, f)| !i || f));
+    }
+
+    #[test]
+    fn bytes_() {
+        let mut filter = CuckooFilter::<12, 2>::with_seed(&SEED);
+        filter.insert(&"AAAA").unwrap();
+        filter.insert(&"BBBB").unwrap();
+
+        let bytes = filter.to_bytes();
+        let other = CuckooFilter::<12, 2>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(filter.len(), other.len());
+        assert_eq!(SEED, other.seed());
+        assert!(other.contains(&"AAAA"));
+        assert!(other.contains(&"BBBB"));
+        assert_eq!(bytes, other.to_bytes());
+
+        assert!(matches!(
+            CuckooFilter::<12, 1>::from_bytes(&bytes),
+            Err(CodecError::InvalidParameters)
+        ));
+        assert!(matches!(
+            CuckooFilter::<12, 2, 12>::from_bytes(&bytes),
+            Err(CodecError::InvalidParameters)
+        ));
+
+        // The length does not match the stored fingerprints.
+        let (mut header, payload) = Header::decode(&bytes).unwrap();
+        header.params[2] = 0;
+        assert!(matches!(
+            CuckooFilter::<12, 2>::from_bytes(&header.encode(payload)),
+            Err(CodecError::InvalidPayload)
+        ));
+    }
+
+    #[test]
+    fn bytes_width_() {
+        let mut filter = CuckooFilter::<16, 3, 13>::new();
+        (0..40usize).for_each(|x| filter.insert(&x).unwrap());
+
+        let bytes = filter.to_bytes();
+        let other = CuckooFilter::<16, 3, 13>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(filter.len(), other.len());
+        assert!((0..40usize).all(|x| other.contains(&x)));
+        assert_eq!(bytes, other.to_bytes());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_() {
+        let mut filter = CuckooFilter::<12, 2>::with_seed(&SEED);
+        filter.insert(&"AAAA").unwrap();
+        filter.insert(&"BBBB").unwrap();
+
+        let json = serde_json::to_string(&filter).unwrap();
+        let other: CuckooFilter<12, 2> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(filter.len(), other.len());
+        assert!(other.contains(&"AAAA"));
+        assert!(other.contains(&"BBBB"));
+
+        assert!(serde_json::from_str::<CuckooFilter<12, 1>>(&json).is_err());
+        assert!(serde_json::from_str::<CuckooFilter<12, 2, 16>>(&json).is_err());
+
+        let json = json.replace("\"len\":2", "\"len\":0");
+        assert!(serde_json::from_str::<CuckooFilter<12, 2>>(&json).is_err());
+    }
 }
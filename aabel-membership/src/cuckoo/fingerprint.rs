@@ -1,7 +1,14 @@
 use std::fmt::Debug;
 
-#[derive(Default, Hash, PartialEq, Eq, Clone, Copy)]
-pub struct Fingerprint(u8);
+/// The smallest number of bits of a fingerprint.
+pub const MIN_FINGERPRINT_BITS: usize = 4;
+
+/// The largest number of bits of a fingerprint.
+pub const MAX_FINGERPRINT_BITS: usize = 32;
+
+/// A fingerprint of an item, never zero so a zero slot marks an empty one.
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+pub struct Fingerprint(u32);
 
 impl Debug for Fingerprint {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -9,14 +16,34 @@ impl Debug for Fingerprint {
     }
 }
 
-impl From<u32> for Fingerprint {
-    fn from(value: u32) -> Self {
-        Self(value as u8)
+impl Fingerprint {
+    /// Maps a hash value to a non zero fingerprint of a given number of bits,
+    /// each of the `2^bits - 1` fingerprints being equally likely.
+    pub fn new(hash: u32, bits: usize) -> Self {
+        assert!(
+            (MIN_FINGERPRINT_BITS..=MAX_FINGERPRINT_BITS).contains(&bits),
+            "the fingerprints must have between 4 and 32 bits"
+        );
+
+        let max = (1u64 << bits) - 1;
+        Self((hash as u64 % max) as u32 + 1)
+    }
+
+    /// Returns the fingerprint stored in a slot, or none if the slot is empty.
+    pub(crate) fn from_slot(value: u32) -> Option<Self> {
+        (value != 0).then_some(Self(value))
     }
 }
 
-impl AsRef<u8> for Fingerprint {
-    fn as_ref(&self) -> &u8 {
+/// Returns the hash which moves a fingerprint between its two buckets, using
+/// the multiplier of MurmurHash2 as the reference implementation does.
+#[inline]
+pub(crate) fn alt_hash(fp: u32) -> usize {
+    (fp as u64).wrapping_mul(0x5bd1_e995) as usize
+}
+
+impl AsRef<u32> for Fingerprint {
+    fn as_ref(&self) -> &u32 {
         &self.0
     }
 }
@@ -27,7 +54,20 @@ mod utests {
 
     #[test]
     fn debug_() {
-        let fp = Fingerprint::from(10);
+        let fp = Fingerprint::new(10, 8);
         println!("FINGERPRINT_DBG: {:?}", fp);
     }
+
+    #[test]
+    fn non_zero_() {
+        for bits in [4, 8, 13, 32] {
+            let max = ((1u64 << bits) - 1) as u32;
+            for hash in [0, 1, max - 1, max, max.wrapping_add(1), u32::MAX] {
+                let fp = *Fingerprint::new(hash, bits).as_ref();
+                assert!(fp != 0 && fp <= max, "bits={bits} hash={hash}");
+            }
+        }
+
+        assert_eq!(None, Fingerprint::from_slot(0));
+    }
 }
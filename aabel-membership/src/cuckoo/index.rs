@@ -9,6 +9,32 @@ impl Debug for Index {
     }
 }
 
+impl Index {
+    /// Returns the bucket of a hash value among *buckets* buckets, masking the
+    /// hash when the number of buckets is a power of two.
+    pub fn bucket(hash: u64, buckets: usize) -> Self {
+        if buckets.is_power_of_two() {
+            Self(hash as usize & (buckets - 1))
+        } else {
+            Self((hash % buckets as u64) as usize)
+        }
+    }
+
+    /// Returns the alternate bucket for a fingerprint hash among *buckets*
+    /// buckets. The mapping is its own inverse, the alternate of the alternate
+    /// bucket is the original one: with a power of two number of buckets the
+    /// hash is xor-ed and masked, otherwise the bucket is subtracted from the
+    /// hash modulo the number of buckets.
+    pub fn alt(self, fp_hash: usize, buckets: usize) -> Self {
+        if buckets.is_power_of_two() {
+            Self((self.0 ^ fp_hash) & (buckets - 1))
+        } else {
+            let h = fp_hash % buckets;
+            Self((h + buckets - self.0 % buckets) % buckets)
+        }
+    }
+}
+
 impl Rem<usize> for Index {
     type Output = usize;
 
@@ -50,6 +76,7 @@ impl AsRef<usize> for Index {
 #[cfg(test)]
 mod utests {
     use super::*;
+    use quickcheck_macros::quickcheck;
 
     #[test]
     fn debug_() {
@@ -69,4 +96,30 @@ mod utests {
         let idx = Index::from(10u32);
         assert_eq!(&10, idx.as_ref())
     }
+
+    #[test]
+    fn bucket_() {
+        assert_eq!(Index::from(5usize), Index::bucket(0xf5, 16));
+        assert_eq!(Index::from(5usize), Index::bucket(17, 12));
+        assert_eq!(Index::from(9usize), Index::from(5usize).alt(0xc, 16));
+        assert_eq!(Index::from(7usize), Index::from(5usize).alt(0xc, 12));
+    }
+
+    #[quickcheck]
+    fn prop_alt_alt(hash: u64, fp_hash: usize, buckets: u16) -> bool {
+        let buckets = buckets as usize + 1;
+        let idx = Index::bucket(hash, buckets);
+        let alt = idx.alt(fp_hash, buckets);
+
+        *idx.as_ref() < buckets && *alt.as_ref() < buckets && alt.alt(fp_hash, buckets) == idx
+    }
+
+    #[quickcheck]
+    fn prop_alt_alt_power_of_two(hash: u64, fp_hash: usize, shift: u8) -> bool {
+        let buckets = 1 << (shift % 32);
+        let idx = Index::bucket(hash, buckets);
+        let alt = idx.alt(fp_hash, buckets);
+
+        *alt.as_ref() < buckets && alt.alt(fp_hash, buckets) == idx
+    }
 }
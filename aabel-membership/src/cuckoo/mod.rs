@@ -2,6 +2,19 @@ pub(crate) mod bucket;
 pub(crate) mod fi_pair;
 pub(crate) mod fingerprint;
 pub(crate) mod index;
+pub(crate) mod table;
+
+mod error;
+pub use error::*;
 
 mod filter;
 pub use filter::*;
+
+mod dynamic;
+pub use dynamic::*;
+
+mod semi_sorted;
+pub use semi_sorted::*;
+
+mod counting;
+pub use counting::*;
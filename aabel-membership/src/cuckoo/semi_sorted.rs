@@ -0,0 +1,743 @@
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
+
+use aabel_codec::{CodecError, Header, SketchKind};
+use aabel_hash::hash::{Hasher128, HasherExt, HasherId, SipHasher24};
+use log::debug;
+use rand::Rng;
+
+use crate::prefetch::{prefetch, BATCH};
+
+use super::{
+    fi_pair::FIPair,
+    fingerprint::{Fingerprint, MAX_FINGERPRINT_BITS, MIN_FINGERPRINT_BITS},
+    CuckooError, DEFAULT_MAX_KICKS,
+};
+
+/// The number of slots of a semi-sorted bucket.
+const SLOTS: usize = 4;
+
+/// The number of sorted sequences of four 4 bits prefixes.
+const SEQUENCES: usize = 3876;
+
+/// The number of bits encoding the sequence of prefixes of a bucket.
+const SEQUENCE_BITS: usize = 12;
+
+/// The sorted sequences of prefixes, packed four bits per prefix.
+static DECODE: [u16; SEQUENCES] = decode_table();
+
+/// The position of each sorted sequence of prefixes in [`DECODE`].
+static ENCODE: [u16; 1 << 16] = encode_table();
+
+const fn decode_table() -> [u16; SEQUENCES] {
+    let mut table = [0; SEQUENCES];
+    let mut n = 0;
+
+    let mut a = 0;
+    while a < 16 {
+        let mut b = a;
+        while b < 16 {
+            let mut c = b;
+            while c < 16 {
+                let mut d = c;
+                while d < 16 {
+                    table[n] = (a | b << 4 | c << 8 | d << 12) as u16;
+                    n += 1;
+                    d += 1;
+                }
+                c += 1;
+            }
+            b += 1;
+        }
+        a += 1;
+    }
+
+    table
+}
+
+const fn encode_table() -> [u16; 1 << 16] {
+    let decode = decode_table();
+    let mut table = [0; 1 << 16];
+
+    let mut i = 0;
+    while i < SEQUENCES {
+        table[decode[i] as usize] = i as u16;
+        i += 1;
+    }
+
+    table
+}
+
+/// A bucket of four fingerprints of *F* bits, stored semi-sorted as in Fan et al.
+///
+/// The fingerprints are sorted by their lowest 4 bits, so the four prefixes
+/// form one of 3876 sorted sequences, encoded on 12 bits instead of 16. The
+/// remaining bits of the fingerprints follow, so a bucket takes `4 * F - 4`
+/// bits, one bit less per fingerprint.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SemiSortedBucket<const F: usize = 9> {
+    slots: [u32; SLOTS],
+}
+
+impl<const F: usize> Default for SemiSortedBucket<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const F: usize> Debug for SemiSortedBucket<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let slots: Vec<String> = (0..SLOTS)
+            .map(|i| match self.get(i) {
+                Some(fp) => format!("{fp:?}"),
+                None => "_".to_owned(),
+            })
+            .collect();
+
+        write!(f, "[{}]", slots.join(" "))
+    }
+}
+
+impl<const F: usize> SemiSortedBucket<F> {
+    /// The number of bits of the remaining part of a fingerprint.
+    const SUFFIX_BITS: usize = F - 4;
+
+    /// The number of bits of an encoded bucket.
+    pub const BITS: usize = SEQUENCE_BITS + SLOTS * Self::SUFFIX_BITS;
+
+    pub fn new() -> Self {
+        const {
+            assert!(
+                F >= MIN_FINGERPRINT_BITS && F <= MAX_FINGERPRINT_BITS,
+                "the fingerprints must have between 4 and 32 bits"
+            );
+        }
+
+        Self { slots: [0; SLOTS] }
+    }
+
+    /// Creates a bucket from its semi-sorted bits, or none if the sequence of
+    /// prefixes is not a valid code or bits past the last slot are set.
+    pub fn from_bits(bits: u128) -> Option<Self> {
+        if (bits & 0xfff) as usize >= SEQUENCES || bits >> Self::BITS != 0 {
+            return None;
+        }
+
+        Some(Self::decode(bits))
+    }
+
+    /// Decodes a bucket from its semi-sorted bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sequence of prefixes is not a valid code.
+    pub fn decode(bits: u128) -> Self {
+        let mut bucket = Self::new();
+        let prefixes = DECODE[(bits & 0xfff) as usize];
+        let mask = (1u128 << Self::SUFFIX_BITS) - 1;
+
+        for (i, slot) in bucket.slots.iter_mut().enumerate() {
+            let prefix = (prefixes >> (4 * i)) as u32 & 0xf;
+            let suffix = (bits >> (SEQUENCE_BITS + i * Self::SUFFIX_BITS)) & mask;
+            *slot = prefix | (suffix as u32) << 4;
+        }
+
+        bucket
+    }
+
+    /// Encodes the bucket, sorting its fingerprints by their prefix.
+    pub fn encode(&self) -> u128 {
+        let mut slots = self.slots;
+        slots.sort_unstable_by_key(|fp| fp & 0xf);
+
+        let prefixes = slots
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, fp)| acc | ((fp & 0xf) as usize) << (4 * i));
+
+        slots
+            .iter()
+            .enumerate()
+            .fold(ENCODE[prefixes] as u128, |acc, (i, fp)| {
+                acc | ((fp >> 4) as u128) << (SEQUENCE_BITS + i * Self::SUFFIX_BITS)
+            })
+    }
+
+    /// Returns the fingerprint stored in a slot.
+    pub fn get(&self, idx: usize) -> Option<Fingerprint> {
+        Fingerprint::from_slot(self.slots[idx])
+    }
+
+    pub fn insert(&mut self, fp: Fingerprint) -> bool {
+        match self.slots.iter().position(|s| *s == 0) {
+            Some(i) => {
+                self.slots[i] = *fp.as_ref();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes one occurrence of a fingerprint, returning false if it is not in the bucket.
+    pub fn remove(&mut self, fp: Fingerprint) -> bool {
+        match self.contains(fp) {
+            Some(i) => {
+                self.slots[i] = 0;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, fp: Fingerprint) -> Option<usize> {
+        self.slots.iter().position(|s| s == fp.as_ref())
+    }
+
+    /// Returns the number of slots holding a fingerprint.
+    pub fn count(&self, fp: Fingerprint) -> usize {
+        self.slots.iter().filter(|s| *s == fp.as_ref()).count()
+    }
+
+    pub fn swap(&mut self, idx: usize, other: Fingerprint) -> Fingerprint {
+        let old = self.get(idx).unwrap();
+        self.slots[idx] = *other.as_ref();
+        old
+    }
+}
+
+/// A cuckoo filter with *B* buckets of four semi-sorted fingerprints of *F*
+/// bits, between 4 and 32.
+///
+/// It has the API and the false positive rate of a `CuckooFilter` with four
+/// slots per bucket, about `8 * load / 2^F`, but each fingerprint takes one bit
+/// less. The buckets are bit-packed in a table on the heap, each one being
+/// decoded to be probed, so the operations are slower.
+pub struct SemiSortedCuckooFilter<const B: usize, const F: usize = 9, H = SipHasher24> {
+    words: Vec<u64>,
+    len: usize,
+    max_kicks: usize,
+    hasher: H,
+}
+
+impl<const B: usize, const F: usize, H> Debug for SemiSortedCuckooFilter<B, F, H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let buckets: Vec<String> = (0..B).map(|i| format!("{:?}", self.bucket(i))).collect();
+        write!(f, "{}", buckets.join(" "))
+    }
+}
+
+impl<const B: usize, const F: usize, H> Default for SemiSortedCuckooFilter<B, F, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const B: usize, const F: usize, H> SemiSortedCuckooFilter<B, F, H> {
+    /// The number of 64 bits words of the table.
+    const WORDS: usize = (B * SemiSortedBucket::<F>::BITS).div_ceil(64);
+
+    /// Returns the number of bits of the buckets.
+    pub fn number_of_bits(&self) -> usize {
+        B * SemiSortedBucket::<F>::BITS
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the maximum number of fingerprints relocated by an insertion.
+    pub fn max_kicks(&self) -> usize {
+        self.max_kicks
+    }
+
+    /// Decodes a bucket of the table.
+    fn bucket(&self, idx: usize) -> SemiSortedBucket<F> {
+        SemiSortedBucket::decode(self.bucket_bits(idx))
+    }
+
+    /// Returns the encoded bits of a bucket of the table.
+    fn bucket_bits(&self, idx: usize) -> u128 {
+        let width = SemiSortedBucket::<F>::BITS;
+        let (mut bits, mut done, pos) = (0u128, 0, idx * width);
+
+        while done < width {
+            let (w, off) = ((pos + done) / 64, (pos + done) % 64);
+            let take = (64 - off).min(width - done);
+            let chunk = (self.words[w] >> off) & (u64::MAX >> (64 - take));
+            bits |= (chunk as u128) << done;
+            done += take;
+        }
+
+        bits
+    }
+
+    /// Encodes a bucket in the table.
+    fn set_bucket(&mut self, idx: usize, bucket: &SemiSortedBucket<F>) {
+        let width = SemiSortedBucket::<F>::BITS;
+        let (bits, mut done, pos) = (bucket.encode(), 0, idx * width);
+
+        while done < width {
+            let (w, off) = ((pos + done) / 64, (pos + done) % 64);
+            let take = (64 - off).min(width - done);
+            let mask = (u64::MAX >> (64 - take)) << off;
+            let chunk = ((bits >> done) as u64) << off;
+            self.words[w] = (self.words[w] & !mask) | (chunk & mask);
+            done += take;
+        }
+    }
+}
+
+impl<const B: usize, const F: usize, H> SemiSortedCuckooFilter<B, F, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt,
+{
+    /// Creates a `SemiSortedCuckooFilter` with the hasher seeded from a random seed.
+    pub fn new() -> Self {
+        let hasher = <H as HasherExt>::with_rnd_seed();
+        Self::with_hasher(hasher)
+    }
+
+    /// Creates a `SemiSortedCuckooFilter` with the hasher seeded from a given seed.
+    pub fn with_seed(seed: &[u8; 16]) -> Self {
+        let hasher = <H as HasherExt>::with_seed(seed);
+        Self::with_hasher(hasher)
+    }
+
+    fn with_hasher(hasher: H) -> Self {
+        const {
+            assert!(
+                F >= MIN_FINGERPRINT_BITS && F <= MAX_FINGERPRINT_BITS,
+                "the fingerprints must have between 4 and 32 bits"
+            );
+        }
+
+        Self {
+            words: vec![0; Self::WORDS],
+            len: 0,
+            max_kicks: DEFAULT_MAX_KICKS,
+            hasher,
+        }
+    }
+
+    /// Returns the seed of the hasher.
+    pub fn seed(&self) -> [u8; 16] {
+        self.hasher.seed()
+    }
+
+    /// Sets the maximum number of fingerprints relocated by an insertion
+    /// before the filter is considered full.
+    pub fn with_max_kicks(mut self, max_kicks: usize) -> Self {
+        self.max_kicks = max_kicks;
+        self
+    }
+
+    /// Inserts an item in the filter.
+    ///
+    /// When both buckets of the item are full, fingerprints picked at random
+    /// are relocated to their alternate bucket. If no free slot is found within
+    /// the maximum number of kicks, the relocations are undone and an error is
+    /// returned, so no previously inserted item is lost.
+    pub fn insert<T>(&mut self, data: &T) -> Result<(), CuckooError>
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        self.insert_pair(fi)
+    }
+
+    pub fn contains<T>(&self, data: &T) -> bool
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        self.contains_pair(fi)
+    }
+
+    /// Returns the number of copies of an item's fingerprint in its two
+    /// buckets, an upper bound of the item's multiplicity.
+    pub fn count<T>(&self, data: &T) -> usize
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        let alt = fi.alt(B);
+
+        let (idx, alt_idx) = (*fi.idx.as_ref(), *alt.idx.as_ref());
+        let n = self.bucket(idx).count(fi.fp);
+        if alt_idx == idx {
+            n
+        } else {
+            n + self.bucket(alt_idx).count(fi.fp)
+        }
+    }
+
+    /// Deletes one occurrence of an item from either of its buckets, returning
+    /// false if the item was not found.
+    ///
+    /// Only inserted items should be deleted, deleting a false positive removes
+    /// the fingerprint of another item.
+    pub fn delete<T>(&mut self, data: &T) -> bool
+    where
+        T: Hash,
+    {
+        let fi = FIPair::from_data(data, self.hasher, F, B);
+        debug!("DELETE | {:?}", &fi);
+
+        self.remove(&fi) || self.remove(&fi.alt(B))
+    }
+
+    /// Inserts a batch of items, returning for each item if it was inserted.
+    ///
+    /// The items are hashed ahead of probing their buckets, so the memory
+    /// loads of several items overlap.
+    pub fn insert_many<T>(&mut self, data: &[T]) -> Vec<bool>
+    where
+        T: Hash,
+    {
+        let mut res = Vec::with_capacity(data.len());
+        let mut fis = Vec::with_capacity(BATCH);
+
+        for chunk in data.chunks(BATCH) {
+            self.batch_pairs(chunk, &mut fis);
+            res.extend(fis.drain(..).map(|fi| self.insert_pair(fi).is_ok()));
+        }
+
+        res
+    }
+
+    /// Determines for each item of a batch if it belongs to the filter.
+    pub fn contains_many<T>(&self, data: &[T]) -> Vec<bool>
+    where
+        T: Hash,
+    {
+        let mut res = Vec::with_capacity(data.len());
+        let mut fis = Vec::with_capacity(BATCH);
+
+        for chunk in data.chunks(BATCH) {
+            self.batch_pairs(chunk, &mut fis);
+            res.extend(fis.drain(..).map(|fi| self.contains_pair(fi)));
+        }
+
+        res
+    }
+
+    /// Hashes a batch of items and prefetches both their buckets.
+    fn batch_pairs<T>(&self, data: &[T], fis: &mut Vec<FIPair>)
+    where
+        T: Hash,
+    {
+        let width = SemiSortedBucket::<F>::BITS;
+        for item in data {
+            let fi = FIPair::from_data(item, self.hasher, F, B);
+            let alt = fi.alt(B);
+
+            prefetch(&self.words, *fi.idx.as_ref() * width / 64);
+            prefetch(&self.words, *alt.idx.as_ref() * width / 64);
+            fis.push(fi);
+        }
+    }
+
+    fn insert_pair(&mut self, fi: FIPair) -> Result<(), CuckooError> {
+        debug!("INSERT | {:?}", &fi);
+
+        if self.put(&fi) || self.put(&fi.alt(B)) {
+            return Ok(());
+        }
+
+        self.rearrange(fi.alt(B))
+    }
+
+    fn contains_pair(&self, fi: FIPair) -> bool {
+        self.bucket(*fi.idx.as_ref()).contains(fi.fp).is_some() || {
+            let fi = fi.alt(B);
+            self.bucket(*fi.idx.as_ref()).contains(fi.fp).is_some()
+        }
+    }
+
+    fn rearrange(&mut self, fi: FIPair) -> Result<(), CuckooError> {
+        debug!("REARRANGE | {:?}", &fi);
+
+        let mut rng = rand::thread_rng();
+        let mut current = fi;
+
+        // The relocated fingerprints, to undo the kicks on failure. The slots
+        // are sorted again when encoded, so a kick is undone by fingerprint.
+        let mut kicked = Vec::new();
+
+        for _ in 0..self.max_kicks {
+            let idx = *current.idx.as_ref();
+            let mut bucket = self.bucket(idx);
+            let other = bucket.swap(rng.gen_range(0..SLOTS), current.fp);
+            self.set_bucket(idx, &bucket);
+            kicked.push((idx, current.fp, other));
+
+            current = FIPair::new(other, current.idx).alt(B);
+            if self.put(&current) {
+                debug!("SUCCESS | {:?} | KICKS {}", current, kicked.len());
+                return Ok(());
+            }
+        }
+
+        // Put back the relocated fingerprints, dropping the new one.
+        for (idx, fp, other) in kicked.into_iter().rev() {
+            let mut bucket = self.bucket(idx);
+            bucket.remove(fp);
+            bucket.insert(other);
+            self.set_bucket(idx, &bucket);
+        }
+
+        debug!("FULL | {} KICKS", self.max_kicks);
+        Err(CuckooError::Full {
+            kicks: self.max_kicks,
+        })
+    }
+
+    fn remove(&mut self, fi: &FIPair) -> bool {
+        let idx = *fi.idx.as_ref();
+        let mut bucket = self.bucket(idx);
+        if bucket.remove(fi.fp) {
+            self.set_bucket(idx, &bucket);
+            self.len -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn put(&mut self, fi: &FIPair) -> bool {
+        let idx = *fi.idx.as_ref();
+        let mut bucket = self.bucket(idx);
+        if bucket.insert(fi.fp) {
+            self.set_bucket(idx, &bucket);
+            self.len += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<const B: usize, const F: usize, H> SemiSortedCuckooFilter<B, F, H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt + HasherId,
+{
+    /// Encodes the packed buckets, their dimensions and the hasher seed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let params = vec![B as u64, F as u64, self.len as u64];
+        let header = Header::new(
+            SketchKind::SemiSortedCuckooFilter,
+            H::HASHER_ID,
+            self.hasher.seed(),
+            params,
+        );
+
+        let payload: Vec<u8> = self.words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        header.encode(&payload)
+    }
+
+    /// Decodes the buckets encoded with [`SemiSortedCuckooFilter::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (header, payload) = Header::decode(bytes)?;
+        header.expect_kind(SketchKind::SemiSortedCuckooFilter)?;
+        header.expect_hasher(H::HASHER_ID)?;
+
+        let [b, f, len] = header.expect_params()?;
+        if b != B as u64 || f != F as u64 {
+            return Err(CodecError::InvalidParameters);
+        }
+
+        if payload.len() != 8 * Self::WORDS {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        let mut filter = Self::with_seed(&header.seed);
+        for (word, bytes) in filter.words.iter_mut().zip(payload.chunks_exact(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        // The bits past the last bucket must be zero.
+        let used = B * SemiSortedBucket::<F>::BITS % 64;
+        if used != 0 && filter.words[Self::WORDS - 1] >> used != 0 {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        let mut used = 0;
+        for idx in 0..B {
+            let bucket = SemiSortedBucket::<F>::from_bits(filter.bucket_bits(idx))
+                .ok_or(CodecError::InvalidPayload)?;
+            used += (0..SLOTS).filter(|&i| bucket.get(i).is_some()).count();
+        }
+
+        // The length is the number of stored fingerprints.
+        if used as u64 != len {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        filter.len = len as usize;
+        Ok(filter)
+    }
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+    use std::collections::HashSet;
+
+    const SEED: [u8; 16] = [42; 16];
+
+    #[test]
+    fn tables_() {
+        assert_eq!(0x0000, DECODE[0]);
+        assert_eq!(0xffff, DECODE[SEQUENCES - 1]);
+        assert_eq!(SEQUENCES, DECODE.iter().collect::<HashSet<_>>().len());
+        assert!((0..SEQUENCES).all(|i| ENCODE[DECODE[i] as usize] as usize == i));
+    }
+
+    #[test]
+    fn from_bits_() {
+        let mut bucket = SemiSortedBucket::<9>::new();
+        assert!(bucket.insert(Fingerprint::new(0x1a3, 9)));
+        let decoded = SemiSortedBucket::<9>::from_bits(bucket.encode()).unwrap();
+        assert_eq!(bucket.encode(), decoded.encode());
+
+        assert_eq!(None, SemiSortedBucket::<9>::from_bits(SEQUENCES as u128));
+        assert_eq!(None, SemiSortedBucket::<9>::from_bits(1 << 32));
+    }
+
+    #[test]
+    fn bucket_() {
+        assert_eq!(32, SemiSortedBucket::<9>::BITS);
+        assert_eq!(124, SemiSortedBucket::<32>::BITS);
+
+        let mut bucket = SemiSortedBucket::<13>::new();
+        let fps: Vec<_> = [0x1a3, 0x004, 0x7f1, 0x1a3]
+            .into_iter()
+            .map(|h| Fingerprint::new(h, 13))
+            .collect();
+        fps.iter().for_each(|fp| assert!(bucket.insert(*fp)));
+        assert!(!bucket.insert(fps[0]));
+
+        // The decoded slots are sorted by prefix, the fingerprints are kept.
+        let decoded = SemiSortedBucket::<13>::decode(bucket.encode());
+        let mut expected = bucket.slots;
+        expected.sort_unstable_by_key(|fp| fp & 0xf);
+        assert_eq!(expected, decoded.slots);
+
+        let mut bucket = decoded;
+        assert!(bucket.remove(fps[3]));
+        assert!(bucket.contains(fps[0]).is_some());
+        let decoded = SemiSortedBucket::<13>::decode(bucket.encode());
+        assert_eq!(3, (0..SLOTS).filter(|&i| decoded.get(i).is_some()).count());
+    }
+
+    #[test]
+    fn simple_() {
+        let mut filter = SemiSortedCuckooFilter::<64>::with_seed(&SEED);
+        assert_eq!(64 * 32, filter.number_of_bits());
+
+        filter.insert(&"AAAA").unwrap();
+        filter.insert(&"AAAA").unwrap();
+        assert!(filter.contains(&"AAAA"));
+        assert_eq!(2, filter.count(&"AAAA"));
+        assert_eq!(2, filter.len());
+
+        assert!(filter.delete(&"AAAA"));
+        assert!(filter.contains(&"AAAA"));
+        assert!(filter.delete(&"AAAA"));
+        assert!(!filter.contains(&"AAAA"));
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn full_() {
+        let mut filter = SemiSortedCuckooFilter::<64, 11>::new();
+
+        // Fill the filter, a failed insertion must not lose any fingerprint.
+        let mut inserted = Vec::new();
+        for x in 0..1_000usize {
+            match filter.insert(&x) {
+                Ok(()) => inserted.push(x),
+                Err(CuckooError::Full { .. }) => {
+                    assert_eq!(inserted.len(), filter.len());
+                    assert!(inserted.iter().all(|x| filter.contains(x)));
+                }
+            }
+        }
+
+        assert!(inserted.len() > 64 * 4 * 9 / 10, "{}", inserted.len());
+    }
+
+    #[test]
+    fn many_() {
+        let mut filter = SemiSortedCuckooFilter::<1024>::new();
+        let xs: Vec<usize> = (0..500).collect();
+        let inserted = filter.insert_many(&xs);
+
+        let ys: Vec<usize> = (250..750).collect();
+        let expected: Vec<bool> = ys.iter().map(|y| filter.contains(y)).collect();
+        assert_eq!(expected, filter.contains_many(&ys));
+
+        let found = filter.contains_many(&xs);
+        assert!(inserted.iter().zip(found).all(|(&i, f)| !i || f));
+    }
+
+    #[test]
+    fn bytes_() {
+        let mut filter = SemiSortedCuckooFilter::<100, 12>::with_seed(&SEED);
+        (0..300usize).for_each(|x| filter.insert(&x).unwrap());
+
+        let bytes = filter.to_bytes();
+        let other = SemiSortedCuckooFilter::<100, 12>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(filter.len(), other.len());
+        assert!((0..300usize).all(|x| other.contains(&x)));
+        assert_eq!(bytes, other.to_bytes());
+
+        assert!(matches!(
+            SemiSortedCuckooFilter::<100, 13>::from_bytes(&bytes),
+            Err(CodecError::InvalidParameters)
+        ));
+
+        // The first bucket does not hold a valid sequence of prefixes.
+        let (mut header, payload) = Header::decode(&bytes).unwrap();
+        let mut invalid = payload.to_vec();
+        invalid[0] = 0xff;
+        invalid[1] |= 0x0f;
+        assert!(matches!(
+            SemiSortedCuckooFilter::<100, 12>::from_bytes(&header.encode(&invalid)),
+            Err(CodecError::InvalidPayload)
+        ));
+
+        // The bits past the last bucket are set.
+        let mut invalid = payload.to_vec();
+        *invalid.last_mut().unwrap() |= 0x80;
+        assert!(matches!(
+            SemiSortedCuckooFilter::<100, 12>::from_bytes(&header.encode(&invalid)),
+            Err(CodecError::InvalidPayload)
+        ));
+
+        // The length does not match the stored fingerprints.
+        header.params[2] = 0;
+        assert!(matches!(
+            SemiSortedCuckooFilter::<100, 12>::from_bytes(&header.encode(payload)),
+            Err(CodecError::InvalidPayload)
+        ));
+    }
+
+    #[quickcheck]
+    fn prop_semi_sorted_cuckoo_filter(xs: HashSet<u32>) -> bool {
+        let mut filter = SemiSortedCuckooFilter::<37, 10>::new();
+        let inserted: Vec<_> = xs.iter().filter(|x| filter.insert(x).is_ok()).collect();
+        inserted.len() == filter.len() && inserted.iter().all(|x| filter.contains(x))
+    }
+}
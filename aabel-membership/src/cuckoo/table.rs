@@ -0,0 +1,89 @@
+/// A table of fingerprints of a given number of bits, packed in 64 bits words.
+/// A zero fingerprint marks an empty slot.
+pub(crate) struct PackedTable {
+    bits: usize,
+    slots: usize,
+    words: Vec<u64>,
+}
+
+impl PackedTable {
+    /// Creates a table with `slots` empty slots of `bits` bits.
+    pub(crate) fn new(slots: usize, bits: usize) -> Self {
+        assert!(
+            (1..=32).contains(&bits),
+            "the fingerprints must have between 1 and 32 bits"
+        );
+
+        Self {
+            bits,
+            slots,
+            words: vec![0; (slots * bits).div_ceil(64)],
+        }
+    }
+
+    /// Returns the number of slots.
+    pub(crate) fn slots(&self) -> usize {
+        self.slots
+    }
+
+    /// Returns the number of bytes used by the slots.
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        self.words.len() * 8
+    }
+
+    #[inline]
+    fn mask(&self) -> u64 {
+        (1 << self.bits) - 1
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, i: usize) -> u32 {
+        let pos = i * self.bits;
+        let (w, off) = (pos / 64, pos % 64);
+
+        let mut v = self.words[w] >> off;
+        if off + self.bits > 64 {
+            v |= self.words[w + 1] << (64 - off);
+        }
+
+        (v & self.mask()) as u32
+    }
+
+    #[inline]
+    pub(crate) fn set(&mut self, i: usize, v: u32) {
+        let pos = i * self.bits;
+        let (w, off) = (pos / 64, pos % 64);
+        let (mask, v) = (self.mask(), v as u64 & self.mask());
+
+        self.words[w] = (self.words[w] & !(mask << off)) | (v << off);
+        if off + self.bits > 64 {
+            let shift = 64 - off;
+            self.words[w + 1] = (self.words[w + 1] & !(mask >> shift)) | (v >> shift);
+        }
+    }
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+
+    #[test]
+    fn get_set_() {
+        for bits in [4, 7, 8, 12, 13, 16, 31, 32] {
+            let mut table = PackedTable::new(100, bits);
+            let max = ((1u64 << bits) - 1) as u32;
+            let value = |i: usize| (i as u32).wrapping_mul(2_654_435_761) & max;
+
+            (0..100).for_each(|i| table.set(i, value(i)));
+            assert!((0..100).all(|i| table.get(i) == value(i)));
+
+            // Setting a slot leaves its neighbours untouched.
+            table.set(50, max);
+            table.set(51, 0);
+            assert_eq!(max, table.get(50));
+            assert_eq!(0, table.get(51));
+            assert_eq!(value(49), table.get(49));
+            assert_eq!(value(52), table.get(52));
+        }
+    }
+}
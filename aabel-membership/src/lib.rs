@@ -3,3 +3,9 @@ pub mod bloom;
 
 #[cfg(feature = "cuckoo")]
 pub mod cuckoo;
+
+#[cfg(feature = "quotient")]
+pub mod quotient;
+
+#[cfg(any(feature = "bloom", feature = "cuckoo"))]
+mod prefetch;
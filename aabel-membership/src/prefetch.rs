@@ -0,0 +1,17 @@
+/// The number of items hashed ahead of probing in the batch operations.
+pub(crate) const BATCH: usize = 16;
+
+/// Hints the CPU to load the cache line of a slice element.
+#[inline(always)]
+pub(crate) fn prefetch<T>(xs: &[T], idx: usize) {
+    #[cfg(target_arch = "x86_64")]
+    if let Some(x) = xs.get(idx) {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+        // SAFETY: prefetching is only a hint and the address is valid anyway.
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(x as *const T as *const i8) };
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = (xs, idx);
+}
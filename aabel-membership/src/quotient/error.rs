@@ -0,0 +1,30 @@
+use std::{error::Error, fmt::Display};
+
+/// The errors returned by the quotient filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotientError {
+    /// All the slots but one are used, the filter must be resized.
+    Full { slots: usize },
+    /// The filter cannot be resized, its remainders having a single bit or its
+    /// quotients the largest number of bits.
+    Exhausted,
+    /// The filters cannot be merged, their seeds or fingerprint widths differ.
+    Incompatible,
+}
+
+impl Display for QuotientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Full { slots } => {
+                write!(f, "the filter is full, {slots} slots with one kept empty")
+            }
+            Self::Exhausted => write!(
+                f,
+                "the filter cannot be resized, no quotient bit can be added"
+            ),
+            Self::Incompatible => write!(f, "the filters have different seeds or fingerprints"),
+        }
+    }
+}
+
+impl Error for QuotientError {}
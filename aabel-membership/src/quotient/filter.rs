@@ -0,0 +1,711 @@
+use std::hash::{Hash, Hasher};
+
+use aabel_codec::{CodecError, Header, SketchKind};
+use aabel_hash::hash::{Hash128Ext, Hasher128, HasherExt, HasherId, SipHasher24};
+use log::debug;
+
+use super::{
+    slots::{Slots, CONTINUATION, METADATA_BITS, OCCUPIED, SHIFTED},
+    QuotientError,
+};
+
+/// The largest number of bits of a quotient.
+pub const MAX_QUOTIENT_BITS: usize = 40;
+
+/// The largest number of bits of a remainder.
+pub const MAX_REMAINDER_BITS: usize = 61;
+
+/// The load factor targeted by [`QuotientFilter::with_capacity_fpr`] and by
+/// the resizes of [`QuotientFilter::merge`].
+pub const MAX_LOAD: f64 = 0.75;
+
+/// A quotient filter storing fingerprints of `q + r` bits in `2^q` slots.
+///
+/// The fingerprint of an item is split in a quotient, its `q` high bits, which
+/// is the canonical slot of the item, and a remainder, its `r` low bits, which
+/// is stored in the slot. The remainders of a quotient are kept sorted in a
+/// run, and the runs are shifted past their canonical slot when it is taken.
+/// Three bits per slot mark the occupied canonical slots, the continued runs
+/// and the shifted remainders. With a load factor `a` the false positive rate
+/// is about `a / 2^r`.
+///
+/// Since the fingerprints are stored whole, the filter is resized by moving
+/// one bit from the remainders to the quotients, and filters with the same seed
+/// and fingerprint width are merged, without hashing the items again. The
+/// copies of an item are stored in separate slots, so it is counted.
+pub struct QuotientFilter<H = SipHasher24> {
+    slots: Slots,
+    /// Number of bits of a quotient.
+    q: usize,
+    /// Number of bits of a remainder.
+    r: usize,
+    len: usize,
+    hasher: H,
+}
+
+impl<H> QuotientFilter<H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt,
+{
+    /// Creates a `QuotientFilter` with `2^quotient_bits` slots storing
+    /// remainders of *remainder_bits* bits.
+    pub fn new(quotient_bits: usize, remainder_bits: usize) -> Self {
+        let hasher = <H as HasherExt>::with_rnd_seed();
+        Self::with_hasher(quotient_bits, remainder_bits, hasher)
+    }
+
+    /// Creates a `QuotientFilter` with `2^quotient_bits` slots storing
+    /// remainders of *remainder_bits* bits, with the hasher seeded from a given seed.
+    pub fn with_seed(quotient_bits: usize, remainder_bits: usize, seed: &[u8; 16]) -> Self {
+        let hasher = <H as HasherExt>::with_seed(seed);
+        Self::with_hasher(quotient_bits, remainder_bits, hasher)
+    }
+
+    fn with_hasher(q: usize, r: usize, hasher: H) -> Self {
+        assert!(
+            (1..=MAX_QUOTIENT_BITS).contains(&q),
+            "the quotients must have between 1 and 40 bits"
+        );
+        assert!(
+            (1..=MAX_REMAINDER_BITS).contains(&r),
+            "the remainders must have between 1 and 61 bits"
+        );
+        assert!(q + r <= 64, "the fingerprints must have at most 64 bits");
+
+        Self {
+            slots: Slots::new(1 << q, r),
+            q,
+            r,
+            len: 0,
+            hasher,
+        }
+    }
+
+    /// Creates a new quotient filter which is expected to store a given number
+    /// of items and with an expected false positive rate.
+    pub fn with_capacity_fpr(num_items: usize, false_positive_rate: f64) -> Self {
+        let (q, r) = compute_optimal(num_items, false_positive_rate);
+        Self::new(q, r)
+    }
+
+    /// Returns the seed of the hasher.
+    pub fn seed(&self) -> [u8; 16] {
+        self.hasher.seed()
+    }
+
+    /// Returns the number of bits of a quotient.
+    pub fn quotient_bits(&self) -> usize {
+        self.q
+    }
+
+    /// Returns the number of bits of a remainder.
+    pub fn remainder_bits(&self) -> usize {
+        self.r
+    }
+
+    /// Returns the number of slots of the filter.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the number of bytes used by the slots.
+    pub fn size_in_bytes(&self) -> usize {
+        self.slots.size_in_bytes()
+    }
+
+    /// Returns the ratio of used slots.
+    pub fn load_factor(&self) -> f64 {
+        self.len as f64 / self.capacity() as f64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of copies of the inserted items.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Inserts a copy of an item in the filter.
+    ///
+    /// One slot is always kept empty, so an error is returned when all the
+    /// others are used, and the filter has to be resized.
+    pub fn insert<T>(&mut self, data: &T) -> Result<(), QuotientError>
+    where
+        T: Hash,
+    {
+        let fp = self.fingerprint(data);
+        self.insert_fingerprint(fp)
+    }
+
+    pub fn contains<T>(&self, data: &T) -> bool
+    where
+        T: Hash,
+    {
+        self.count(data) > 0
+    }
+
+    /// Returns the number of stored copies of an item's fingerprint, an upper
+    /// bound of the item's multiplicity.
+    pub fn count<T>(&self, data: &T) -> usize
+    where
+        T: Hash,
+    {
+        let (fq, fr) = self.split(self.fingerprint(data));
+        if !self.slots.is(fq, OCCUPIED) {
+            return 0;
+        }
+
+        let mut s = self.run_start(fq);
+        let mut n = 0;
+        loop {
+            if self.slots.remainder(s) == fr {
+                n += 1;
+            }
+
+            s = self.next(s);
+            if !self.slots.is(s, CONTINUATION) {
+                break n;
+            }
+        }
+    }
+
+    /// Deletes one copy of an item, returning false if the item was not found.
+    ///
+    /// Only inserted items should be deleted, deleting a false positive removes
+    /// the fingerprint of another item.
+    pub fn delete<T>(&mut self, data: &T) -> bool
+    where
+        T: Hash,
+    {
+        let (fq, fr) = self.split(self.fingerprint(data));
+        debug!("DELETE | {fq} | {fr}");
+
+        if !self.slots.is(fq, OCCUPIED) {
+            return false;
+        }
+
+        let start = self.segment_start(fq);
+        let mut items = self.decode(start);
+        match items.iter().position(|&item| item == (fq, fr)) {
+            Some(i) => {
+                let old_len = items.len();
+                items.remove(i);
+                self.encode(start, old_len, &items);
+                self.len -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Doubles the number of slots, moving the highest bit of the remainders
+    /// to the quotients. The items are not hashed again, the false positive
+    /// rate for a given load factor doubles.
+    pub fn resize(&mut self) -> Result<(), QuotientError> {
+        *self = self.resized(self.q + 1)?;
+        Ok(())
+    }
+
+    /// Inserts the items of another filter with the same seed and fingerprint
+    /// width, resizing the filter while the items would fill more than 75% of
+    /// its slots.
+    pub fn merge(&mut self, other: &Self) -> Result<(), QuotientError> {
+        if self.seed() != other.seed() || self.q + self.r != other.q + other.r {
+            return Err(QuotientError::Incompatible);
+        }
+
+        // The filter is resized at once, and left unchanged if it cannot be.
+        let len = self.len + other.len;
+        let mut q = self.q;
+        while len as f64 > MAX_LOAD * (1u64 << q) as f64 {
+            q += 1;
+        }
+
+        if q > self.q {
+            *self = self.resized(q)?;
+        }
+
+        for fp in other.fingerprints() {
+            self.insert_fingerprint(fp)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns a copy of the filter with quotients of *q* bits, taken from
+    /// the highest bits of the remainders.
+    fn resized(&self, q: usize) -> Result<Self, QuotientError> {
+        if q > MAX_QUOTIENT_BITS || q >= self.q + self.r {
+            return Err(QuotientError::Exhausted);
+        }
+
+        let mut filter = Self::with_hasher(q, self.q + self.r - q, self.hasher);
+        for fp in self.fingerprints() {
+            filter.insert_fingerprint(fp)?;
+        }
+
+        debug!("RESIZE | {} SLOTS", filter.capacity());
+        Ok(filter)
+    }
+
+    /// Returns the fingerprint of an item, the low `q + r` bits of its hash.
+    fn fingerprint<T>(&self, data: &T) -> u64
+    where
+        T: Hash,
+    {
+        let (h, _) = data.get_hash128_deconstructed(self.hasher);
+        h & (u64::MAX >> (64 - self.q - self.r))
+    }
+
+    /// Splits a fingerprint in its quotient and its remainder.
+    fn split(&self, fp: u64) -> (usize, u64) {
+        ((fp >> self.r) as usize, fp & ((1 << self.r) - 1))
+    }
+
+    fn insert_fingerprint(&mut self, fp: u64) -> Result<(), QuotientError> {
+        let (fq, fr) = self.split(fp);
+        debug!("INSERT | {fq} | {fr}");
+
+        if self.len + 1 >= self.capacity() {
+            return Err(QuotientError::Full {
+                slots: self.capacity(),
+            });
+        }
+
+        if self.slots.is_empty(fq) {
+            self.slots.set(fq, fr << METADATA_BITS | OCCUPIED);
+        } else {
+            // The remainders of a quotient are sorted, the runs by quotient.
+            let start = self.segment_start(fq);
+            let mut items = self.decode(start);
+            let key = (self.offset(start, fq), fr);
+            let i = items.partition_point(|&(q, r)| (self.offset(start, q), r) <= key);
+
+            let old_len = items.len();
+            items.insert(i, (fq, fr));
+            self.encode(start, old_len, &items);
+        }
+
+        self.len += 1;
+        Ok(())
+    }
+
+    #[inline]
+    fn next(&self, i: usize) -> usize {
+        (i + 1) & (self.capacity() - 1)
+    }
+
+    #[inline]
+    fn prev(&self, i: usize) -> usize {
+        (i + self.capacity() - 1) & (self.capacity() - 1)
+    }
+
+    /// Returns the distance from a slot to another one, going forward.
+    #[inline]
+    fn offset(&self, start: usize, i: usize) -> usize {
+        (i + self.capacity() - start) & (self.capacity() - 1)
+    }
+
+    /// Returns the slot of the first remainder of an occupied quotient.
+    fn run_start(&self, fq: usize) -> usize {
+        // Walk back to the start of the cluster, which is in its canonical slot.
+        let mut b = fq;
+        while self.slots.is(b, SHIFTED) {
+            b = self.prev(b);
+        }
+
+        // Walk forward, one run per occupied quotient, up to the run of fq.
+        let mut s = b;
+        while b != fq {
+            loop {
+                s = self.next(s);
+                if !self.slots.is(s, CONTINUATION) {
+                    break;
+                }
+            }
+
+            loop {
+                b = self.next(b);
+                if self.slots.is(b, OCCUPIED) {
+                    break;
+                }
+            }
+        }
+
+        s
+    }
+
+    /// Returns the first slot of the sequence of used slots holding a used slot.
+    fn segment_start(&self, i: usize) -> usize {
+        let mut s = i;
+        while !self.slots.is_empty(self.prev(s)) {
+            s = self.prev(s);
+        }
+
+        s
+    }
+
+    /// Returns the quotients and the remainders of the sequence of used slots
+    /// starting at a slot.
+    fn decode(&self, start: usize) -> Vec<(usize, u64)> {
+        let mut items = Vec::new();
+        let mut quotients = std::collections::VecDeque::new();
+        let (mut i, mut q) = (start, start);
+
+        while !self.slots.is_empty(i) {
+            if self.slots.is(i, OCCUPIED) {
+                quotients.push_back(i);
+            }
+
+            if !self.slots.is(i, CONTINUATION) {
+                q = quotients.pop_front().unwrap_or(i);
+            }
+
+            items.push((q, self.slots.remainder(i)));
+            i = self.next(i);
+        }
+
+        items
+    }
+
+    /// Replaces the *old_len* used slots starting at a slot with the sorted
+    /// quotients and remainders, each one in the first free slot from its
+    /// canonical one.
+    fn encode(&mut self, start: usize, old_len: usize, items: &[(usize, u64)]) {
+        let mut i = start;
+        for _ in 0..old_len {
+            self.slots.set(i, 0);
+            i = self.next(i);
+        }
+
+        let mut cursor = 0;
+        let mut prev_q = None;
+        for &(q, r) in items {
+            let pos_offset = cursor.max(self.offset(start, q));
+            let pos = (start + pos_offset) & (self.capacity() - 1);
+
+            let mut v = self.slots.get(pos) & OCCUPIED | r << METADATA_BITS;
+            if pos != q {
+                v |= SHIFTED;
+            }
+            if prev_q == Some(q) {
+                v |= CONTINUATION;
+            }
+
+            self.slots.set(pos, v);
+            self.slots.set(q, self.slots.get(q) | OCCUPIED);
+
+            cursor = pos_offset + 1;
+            prev_q = Some(q);
+        }
+    }
+
+    /// Returns the fingerprints of the stored items.
+    fn fingerprints(&self) -> Vec<u64> {
+        let mut fps = Vec::with_capacity(self.len);
+        let Some(empty) = (0..self.capacity()).find(|&i| self.slots.is_empty(i)) else {
+            return fps;
+        };
+
+        // Decode the sequences of used slots, starting after an empty one.
+        let mut k = 1;
+        while k <= self.capacity() {
+            let i = (empty + k) & (self.capacity() - 1);
+            if self.slots.is_empty(i) {
+                k += 1;
+                continue;
+            }
+
+            let items = self.decode(i);
+            k += items.len();
+            fps.extend(items.iter().map(|&(q, r)| (q as u64) << self.r | r));
+        }
+
+        fps
+    }
+}
+
+impl<H> QuotientFilter<H>
+where
+    H: Copy + Hasher + Hasher128 + HasherExt + HasherId,
+{
+    /// Encodes the slots, their dimensions and the hasher seed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let params = vec![self.q as u64, self.r as u64, self.len as u64];
+        let header = Header::new(
+            SketchKind::QuotientFilter,
+            H::HASHER_ID,
+            self.hasher.seed(),
+            params,
+        );
+
+        let payload: Vec<u8> = self
+            .slots
+            .words()
+            .iter()
+            .flat_map(|w| w.to_le_bytes())
+            .collect();
+        header.encode(&payload)
+    }
+
+    /// Decodes the slots encoded with [`QuotientFilter::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let (header, payload) = Header::decode(bytes)?;
+        header.expect_kind(SketchKind::QuotientFilter)?;
+        header.expect_hasher(H::HASHER_ID)?;
+
+        let [q, r, len] = header.expect_params()?;
+        let (q, r) = (q as usize, r as usize);
+        if !(1..=MAX_QUOTIENT_BITS).contains(&q)
+            || !(1..=MAX_REMAINDER_BITS).contains(&r)
+            || q + r > 64
+            || len as usize >= 1 << q
+        {
+            return Err(CodecError::InvalidParameters);
+        }
+
+        if payload.len() % 8 != 0 {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        let words = payload
+            .chunks_exact(8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+
+        let mut filter = Self::with_seed(q, r, &header.seed);
+        filter.slots = Slots::from_words(1 << q, r, words).ok_or(CodecError::InvalidPayload)?;
+        filter.len = len as usize;
+
+        // The slots must be laid out as their fingerprints are inserted, which
+        // rejects a table without an empty slot, inconsistent metadata bits,
+        // a different number of used slots and padding bits set.
+        let mut expected = Self::with_hasher(q, r, filter.hasher);
+        for fp in filter.fingerprints() {
+            expected
+                .insert_fingerprint(fp)
+                .map_err(|_| CodecError::InvalidPayload)?;
+        }
+
+        if expected.len != filter.len || expected.slots.words() != filter.slots.words() {
+            return Err(CodecError::InvalidPayload);
+        }
+
+        Ok(filter)
+    }
+}
+
+/// Returns the number of bits of the quotients and of the remainders.
+fn compute_optimal(items: usize, fpr: f64) -> (usize, usize) {
+    let slots = (items.max(1) as f64 / MAX_LOAD).ceil() as usize;
+    let q = (slots.next_power_of_two().trailing_zeros() as usize).clamp(1, MAX_QUOTIENT_BITS);
+
+    // The rate is at most the load factor divided by 2^r.
+    let r = (1.0 / fpr).log2().ceil() as usize;
+    (q, r.clamp(1, (64 - q).min(MAX_REMAINDER_BITS)))
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+    use std::collections::HashMap;
+
+    const SEED: [u8; 16] = [42; 16];
+
+    #[test]
+    fn simple_() {
+        let mut filter = QuotientFilter::<SipHasher24>::with_seed(8, 8, &SEED);
+        assert_eq!(256, filter.capacity());
+        assert!(!filter.contains(&"AAAA"));
+
+        filter.insert(&"AAAA").unwrap();
+        filter.insert(&"BBBB").unwrap();
+        filter.insert(&"AAAA").unwrap();
+        assert!(filter.contains(&"AAAA"));
+        assert!(filter.contains(&"BBBB"));
+        assert_eq!(2, filter.count(&"AAAA"));
+        assert_eq!(3, filter.len());
+
+        assert!(filter.delete(&"AAAA"));
+        assert_eq!(1, filter.count(&"AAAA"));
+        assert!(filter.delete(&"AAAA"));
+        assert!(!filter.contains(&"AAAA"));
+        assert!(!filter.delete(&"AAAA"));
+        assert!(filter.contains(&"BBBB"));
+        assert_eq!(1, filter.len());
+    }
+
+    #[test]
+    fn optimal_() {
+        assert_eq!((11, 7), compute_optimal(1000, 0.01));
+        assert_eq!((1, 10), compute_optimal(0, 0.001));
+        assert_eq!((21, 43), compute_optimal(1_000_000, 1e-13));
+    }
+
+    #[test]
+    fn full_() {
+        let mut filter = QuotientFilter::<SipHasher24>::new(4, 8);
+
+        // One slot is kept empty.
+        (0..15usize).for_each(|x| filter.insert(&x).unwrap());
+        assert_eq!(
+            Err(QuotientError::Full { slots: 16 }),
+            filter.insert(&15usize)
+        );
+        assert!((0..15usize).all(|x| filter.contains(&x)));
+    }
+
+    #[test]
+    fn resize_() {
+        let mut filter = QuotientFilter::<SipHasher24>::new(6, 3);
+        (0..50usize).for_each(|x| filter.insert(&x).unwrap());
+
+        filter.resize().unwrap();
+        assert_eq!((7, 2), (filter.quotient_bits(), filter.remainder_bits()));
+        assert_eq!(50, filter.len());
+        assert!((0..50usize).all(|x| filter.contains(&x)));
+
+        filter.resize().unwrap();
+        assert_eq!(Err(QuotientError::Exhausted), filter.resize());
+        assert!((0..50usize).all(|x| filter.contains(&x)));
+
+        // The quotients cannot grow past their largest number of bits, the
+        // slots are kept small to not allocate the whole table.
+        let mut filter = QuotientFilter::<SipHasher24>::with_seed(2, 8, &SEED);
+        filter.q = MAX_QUOTIENT_BITS;
+        assert_eq!(Err(QuotientError::Exhausted), filter.resize());
+    }
+
+    #[test]
+    fn merge_() {
+        let mut filter1 = QuotientFilter::<SipHasher24>::with_seed(6, 10, &SEED);
+        let mut filter2 = QuotientFilter::<SipHasher24>::with_seed(8, 8, &SEED);
+        (0..40usize).for_each(|x| filter1.insert(&x).unwrap());
+        (30..100usize).for_each(|x| filter2.insert(&x).unwrap());
+
+        // The merged items need more than 64 slots.
+        filter1.merge(&filter2).unwrap();
+        assert_eq!((8, 8), (filter1.quotient_bits(), filter1.remainder_bits()));
+        assert_eq!(110, filter1.len());
+        assert!((0..100usize).all(|x| filter1.contains(&x)));
+        assert!((30..40usize).all(|x| filter1.count(&x) >= 2));
+
+        let other = QuotientFilter::<SipHasher24>::with_seed(8, 9, &SEED);
+        assert_eq!(Err(QuotientError::Incompatible), filter1.merge(&other));
+        let other = QuotientFilter::<SipHasher24>::new(8, 8);
+        assert_eq!(Err(QuotientError::Incompatible), filter1.merge(&other));
+
+        // The merged items need 32 slots, one remainder bit cannot be left.
+        let mut filter1 = QuotientFilter::<SipHasher24>::with_seed(3, 2, &SEED);
+        let mut filter2 = QuotientFilter::<SipHasher24>::with_seed(3, 2, &SEED);
+        (0..7usize).for_each(|x| filter1.insert(&x).unwrap());
+        (7..14usize).for_each(|x| filter2.insert(&x).unwrap());
+
+        let bytes = filter1.to_bytes();
+        assert_eq!(Err(QuotientError::Exhausted), filter1.merge(&filter2));
+        assert_eq!(bytes, filter1.to_bytes());
+    }
+
+    #[test]
+    fn fpr_() {
+        let mut filter = QuotientFilter::<SipHasher24>::with_capacity_fpr(10_000, 0.01);
+        (0..10_000usize).for_each(|x| filter.insert(&x).unwrap());
+        assert!((0..10_000usize).all(|x| filter.contains(&x)));
+
+        let fps = (10_000..110_000usize)
+            .filter(|x| filter.contains(x))
+            .count();
+        assert!(fps < 1_000, "{fps}");
+    }
+
+    #[test]
+    fn bytes_() {
+        let mut filter = QuotientFilter::<SipHasher24>::with_seed(7, 11, &SEED);
+        (0..90usize).for_each(|x| filter.insert(&x).unwrap());
+
+        let bytes = filter.to_bytes();
+        let other = QuotientFilter::<SipHasher24>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(filter.len(), other.len());
+        assert!((0..90usize).all(|x| other.contains(&x)));
+        assert_eq!(bytes, other.to_bytes());
+    }
+
+    #[test]
+    fn bytes_invalid_() {
+        // 32 slots of 7 bits, the last word is half padding.
+        let mut filter = QuotientFilter::<SipHasher24>::with_seed(5, 4, &SEED);
+        (0..20usize).for_each(|x| filter.insert(&x).unwrap());
+
+        let bytes = filter.to_bytes();
+        let (header, payload) = Header::decode(&bytes).unwrap();
+        let decode = |header: &Header, payload: &[u8]| {
+            QuotientFilter::<SipHasher24>::from_bytes(&header.encode(payload)).err()
+        };
+        assert_eq!(None, decode(&header, payload));
+
+        // No slot is empty.
+        let full = vec![0xff; payload.len()];
+        assert_eq!(Some(CodecError::InvalidPayload), decode(&header, &full));
+
+        // The number of used slots is not the length.
+        let mut other = header.clone();
+        other.params[2] = 19;
+        assert_eq!(Some(CodecError::InvalidPayload), decode(&other, payload));
+
+        // A used slot is marked as shifted from its canonical slot.
+        let used = (0..32).find(|&i| !filter.slots.is(i, SHIFTED) && !filter.slots.is_empty(i));
+        let mut slots = Slots::from_words(32, 4, filter.slots.words().to_vec()).unwrap();
+        let i = used.unwrap();
+        slots.set(i, slots.get(i) | SHIFTED);
+        let shifted: Vec<u8> = slots.words().iter().flat_map(|w| w.to_le_bytes()).collect();
+        assert_eq!(Some(CodecError::InvalidPayload), decode(&header, &shifted));
+
+        // The padding bits are set.
+        let mut padded = payload.to_vec();
+        *padded.last_mut().unwrap() |= 0x80;
+        assert_eq!(Some(CodecError::InvalidPayload), decode(&header, &padded));
+    }
+
+    #[quickcheck]
+    fn prop_quotient_filter(ops: Vec<(bool, u16)>) -> bool {
+        // With 4 bits remainders, many items share a fingerprint, and the
+        // small table has long clusters wrapping around its end.
+        let mut filter = QuotientFilter::<SipHasher24>::new(6, 4);
+        let mut model = HashMap::<u64, usize>::new();
+
+        for (insert, x) in ops {
+            let fp = filter.fingerprint(&x);
+            if insert {
+                if filter.insert(&x).is_ok() {
+                    *model.entry(fp).or_default() += 1;
+                }
+            } else {
+                let n = model.entry(fp).or_default();
+                if filter.delete(&x) != (*n > 0) {
+                    return false;
+                }
+                *n = n.saturating_sub(1);
+            }
+        }
+
+        let mut fps = filter.fingerprints();
+        fps.sort_unstable();
+        let mut expected: Vec<u64> = model
+            .iter()
+            .flat_map(|(&fp, &n)| std::iter::repeat_n(fp, n))
+            .collect();
+        expected.sort_unstable();
+
+        fps == expected
+            && filter.len() == expected.len()
+            && QuotientFilter::<SipHasher24>::from_bytes(&filter.to_bytes()).is_ok()
+            && (0..=u16::MAX).step_by(97).all(|x| {
+                let n = model.get(&filter.fingerprint(&x)).copied().unwrap_or(0);
+                filter.count(&x) == n
+            })
+    }
+}
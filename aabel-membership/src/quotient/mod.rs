@@ -0,0 +1,7 @@
+pub(crate) mod slots;
+
+mod error;
+pub use error::*;
+
+mod filter;
+pub use filter::*;
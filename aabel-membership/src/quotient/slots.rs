@@ -0,0 +1,127 @@
+/// The slots of a quotient filter, each one packing a remainder of *r* bits
+/// after the occupied, continuation and shifted bits, in 64 bits words.
+pub(crate) struct Slots {
+    width: usize,
+    len: usize,
+    words: Vec<u64>,
+}
+
+/// The bit set when the canonical slot of a quotient has a run.
+pub(crate) const OCCUPIED: u64 = 1;
+
+/// The bit set when the slot continues the run of the previous slot.
+pub(crate) const CONTINUATION: u64 = 2;
+
+/// The bit set when the remainder is not in its canonical slot.
+pub(crate) const SHIFTED: u64 = 4;
+
+/// The number of metadata bits of a slot.
+pub(crate) const METADATA_BITS: usize = 3;
+
+impl Slots {
+    /// Creates `len` empty slots with remainders of `remainder_bits` bits.
+    pub(crate) fn new(len: usize, remainder_bits: usize) -> Self {
+        let width = remainder_bits + METADATA_BITS;
+        Self {
+            width,
+            len,
+            words: vec![0; Self::size_in_words(len, width)],
+        }
+    }
+
+    /// Creates the slots from their packed words.
+    pub(crate) fn from_words(len: usize, remainder_bits: usize, words: Vec<u64>) -> Option<Self> {
+        let width = remainder_bits + METADATA_BITS;
+        if words.len() != Self::size_in_words(len, width) {
+            return None;
+        }
+
+        Some(Self { width, len, words })
+    }
+
+    fn size_in_words(len: usize, width: usize) -> usize {
+        (len * width).div_ceil(64)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Returns the number of bytes used by the slots.
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        self.words.len() * 8
+    }
+
+    /// Returns a slot, its metadata bits followed by its remainder.
+    #[inline]
+    pub(crate) fn get(&self, i: usize) -> u64 {
+        let mask = u64::MAX >> (64 - self.width);
+        let (w, off) = (i * self.width / 64, i * self.width % 64);
+
+        let mut v = self.words[w] >> off;
+        if off + self.width > 64 {
+            v |= self.words[w + 1] << (64 - off);
+        }
+
+        v & mask
+    }
+
+    #[inline]
+    pub(crate) fn set(&mut self, i: usize, v: u64) {
+        let mask = u64::MAX >> (64 - self.width);
+        let (w, off) = (i * self.width / 64, i * self.width % 64);
+
+        self.words[w] = (self.words[w] & !(mask << off)) | ((v & mask) << off);
+        if off + self.width > 64 {
+            let shift = 64 - off;
+            self.words[w + 1] = (self.words[w + 1] & !(mask >> shift)) | ((v & mask) >> shift);
+        }
+    }
+
+    /// Returns the remainder stored in a slot.
+    #[inline]
+    pub(crate) fn remainder(&self, i: usize) -> u64 {
+        self.get(i) >> METADATA_BITS
+    }
+
+    #[inline]
+    pub(crate) fn is(&self, i: usize, bit: u64) -> bool {
+        self.get(i) & bit != 0
+    }
+
+    /// Returns true when a slot holds no remainder.
+    #[inline]
+    pub(crate) fn is_empty(&self, i: usize) -> bool {
+        self.get(i) & (OCCUPIED | CONTINUATION | SHIFTED) == 0
+    }
+}
+
+#[cfg(test)]
+mod utests {
+    use super::*;
+
+    #[test]
+    fn get_set_() {
+        for bits in [1, 5, 13, 29, 61] {
+            let mut slots = Slots::new(37, bits);
+            let mask = u64::MAX >> (64 - bits - METADATA_BITS);
+
+            let value = |i: usize| (i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) & mask;
+            (0..37).for_each(|i| slots.set(i, value(i)));
+            assert!((0..37).all(|i| slots.get(i) == value(i)));
+
+            slots.set(11, OCCUPIED | SHIFTED);
+            assert!(slots.is(11, SHIFTED) && !slots.is(11, CONTINUATION));
+            assert_eq!(0, slots.remainder(11));
+            assert_eq!(value(10), slots.get(10));
+            assert_eq!(value(12), slots.get(12));
+
+            slots.set(12, 0);
+            assert!(slots.is_empty(12));
+        }
+    }
+}
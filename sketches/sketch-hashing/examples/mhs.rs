@@ -51,4 +51,4 @@ fn mhs() {
 
 fn main() {
     mhs()
-}
\ No newline at end of file
+}
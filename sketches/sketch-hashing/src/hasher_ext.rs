@@ -16,7 +16,7 @@ impl<H: Hasher> HasherExt for H {
 mod utests {
     use std::cmp::min;
 
-    use crate::{random, UniversalHasher, MyHasher, min_hash_sig, sim1};
+    use crate::{min_hash_sig, random, sim1, MyHasher, UniversalHasher};
 
     use super::*;
 
@@ -35,7 +35,7 @@ mod utests {
 
     fn pretty_usize(v: usize) -> String {
         if v == usize::MAX {
-            format!("__")
+            "__".to_owned()
         } else {
             format!("{v:02}")
         }
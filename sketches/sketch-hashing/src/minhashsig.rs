@@ -1,10 +1,6 @@
 use std::cmp::min;
 
-pub fn min_hash_sig(
-    documents: &Vec<Vec<i32>>,
-    indexes: &Vec<Vec<usize>>,
-    f: usize,
-) -> Vec<Vec<usize>> {
+pub fn min_hash_sig(documents: &[Vec<i32>], indexes: &[Vec<usize>], f: usize) -> Vec<Vec<usize>> {
     let h = indexes.len();
     let d = documents.len();
 
@@ -28,4 +24,3 @@ pub fn min_hash_sig(
 
     mhs
 }
-
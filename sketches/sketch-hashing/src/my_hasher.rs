@@ -1,5 +1,5 @@
-use std::hash::{ Hash, Hasher};
 use crate::HashExt;
+use std::hash::{Hash, Hasher};
 
 pub struct MyHasher {
     bytes: Vec<u8>,
@@ -28,7 +28,7 @@ impl Hasher for MyHasher {
     fn finish(&self) -> u64 {
         let mut h = 0u64;
         self.bytes.iter().enumerate().for_each(|(i, b)| {
-            h += (*b as u64) << i * 8;
+            h += (*b as u64) << (i * 8);
         });
 
         (self.k * h + self.q) % self.p
@@ -38,4 +38,3 @@ impl Hasher for MyHasher {
         self.bytes.extend(bytes);
     }
 }
-
@@ -1,4 +1,4 @@
-pub fn sim1(mhsig: &Vec<Vec<usize>>, d1: usize, d2: usize) -> (usize, usize) {
+pub fn sim1(mhsig: &[Vec<usize>], d1: usize, d2: usize) -> (usize, usize) {
     let mut cmn = 0usize;
     let mut ttl = 0usize;
 
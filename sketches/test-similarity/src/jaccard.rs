@@ -90,7 +90,7 @@ mod utests {
 
     #[test]
     fn jaccard_similarity_sorted_() {
-        let xs = vec![1, 2, 3, 4, 5, 6];
+        let xs = [1, 2, 3, 4, 5, 6];
         let ys = vec![3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14];
 
         let j1 = jaccard_similarity_sorted(&mut xs.iter(), &mut ys.iter());
@@ -1,7 +1,6 @@
-use std::ops::Deref;
-
 use sprs::CsMat;
 
 pub struct Matrix<N> {
+    #[allow(dead_code)]
     matrix: CsMat<N>,
 }